@@ -0,0 +1,899 @@
+// Emit the C header for an API model
+
+use crate::model::{
+	Api, Constructor, Enum, Field, Function, Ownership, Param, Primitive,
+	Struct, Trait, TypeRef, Utf8Policy, STATUS_CODES,
+};
+use crate::naming::{c_identifier, identifier};
+use convert_case::{Case, Casing};
+
+// Generate the C declarations for every item in the model
+pub fn generate(api: &Api) -> String {
+	let mut c_bindings = vec![
+		"#include <stdbool.h>\n#include <stddef.h>\n#include <stdint.h>\n"
+			.to_string(),
+		generate_c_error_handling(api),
+		format!(
+			"// Releases a string returned from Rust as `char*`; `const char*` strings\n// returned from Rust are borrowed and must not be freed\nvoid {}(char* string);",
+			string_free_name(api)
+		),
+	];
+
+	// Public aliases of primitives keep their name for C code, though
+	// signatures still spell out the primitive. C has no modules, so only
+	// the first alias with a given name is declared
+	let mut typedefs = Vec::new();
+	for alias in api.type_aliases.iter().filter(|a| a.public) {
+		let name = alias.name.to_case(Case::Snake);
+		if let (TypeRef::Primitive(primitive), true) =
+			(&alias.target, alias.params.is_empty())
+		{
+			if !typedefs.contains(&name) {
+				c_bindings.push(format!(
+					"typedef {} {};",
+					primitive_to_c(*primitive),
+					name
+				));
+				typedefs.push(name);
+			}
+		}
+	}
+
+	for enum_item in api.enums.iter().filter(|e| e.is_c_like()) {
+		c_bindings.push(generate_c_enum_binding(enum_item));
+		c_bindings.extend(generate_c_enum_formatting_functions(api, enum_item));
+	}
+
+	for enum_item in tagged_unions_in_dependency_order(api) {
+		c_bindings.push(generate_c_tagged_union_binding(api, enum_item));
+		c_bindings.extend(generate_c_enum_formatting_functions(api, enum_item));
+	}
+
+	for element in api.vec_element_types() {
+		c_bindings.push(generate_c_vec_binding(api, element));
+	}
+
+	for value in api.option_value_types() {
+		c_bindings.push(format!(
+			"// An optional value, `value` is only meaningful when `has_value` is true\ntypedef struct {name} {{\n    bool has_value;\n    {value} value;\n}} {name};",
+			name = option_type(api, value),
+			value = rust_type_to_c(api, value),
+		));
+	}
+
+	// Opaque handles are declared before any prototype, since functions and
+	// accessors may use structs declared further down
+	for struct_item in api.structs.iter().filter(|s| !s.by_value) {
+		c_bindings.push(format!(
+			"// Opaque handle to a Rust `{}`, only usable through pointers\ntypedef struct {1} {1};",
+			struct_item.name,
+			api.c_type_name(&struct_item.name)
+		));
+	}
+
+	for struct_item in structs_in_dependency_order(api) {
+		c_bindings.push(generate_c_struct_binding(api, struct_item));
+	}
+
+	for item in api.iterator_item_types() {
+		c_bindings.push(generate_c_iterator_binding(api, item));
+	}
+
+	for (struct_item, item) in api.iterator_structs() {
+		c_bindings.push(format!(
+			"{}bool {name}_next({name}* iter, {}* out);",
+			iterator_next_comment(api, item),
+			return_type_to_c(api, item),
+			name = api.c_type_name(&struct_item.name),
+		));
+	}
+
+	for trait_item in &api.traits {
+		c_bindings.push(generate_c_trait_vtable(api, trait_item));
+	}
+
+	for trait_item in api.traits.iter().filter(|t| t.c_implementable) {
+		c_bindings.push(generate_c_trait_callbacks(api, trait_item));
+	}
+
+	for (trait_item, struct_item) in api.exported_trait_impls() {
+		let comment = if struct_item.by_value {
+			format!(
+				"// `free` does nothing, as `{}` values belong to the caller\n",
+				api.c_type_name(&struct_item.name)
+			)
+		} else {
+			String::new()
+		};
+		c_bindings.push(format!(
+			"{}const {}_vtable* {}_{}_vtable(void);",
+			comment,
+			trait_item.name.to_case(Case::Snake),
+			api.c_type_name(&struct_item.name),
+			trait_item.name.to_case(Case::Snake)
+		));
+	}
+
+	for func in &api.functions {
+		c_bindings.push(generate_c_binding(api, func));
+	}
+
+	c_bindings.join("\n")
+}
+
+// Generate the C prototype for a Rust function
+fn generate_c_binding(api: &Api, func: &Function) -> String {
+	let params = func
+		.inputs
+		.iter()
+		.flat_map(|param| c_params(api, param, api.utf8_policy(func)))
+		.chain(out_param(api, &func.inputs, &func.output))
+		.collect::<Vec<_>>();
+
+	let mut binding = String::new();
+	if func.output.out_value().is_some() {
+		binding.push_str(&format!(
+			"// Writes the result to `{}` when returning {}\n",
+			out_param_name(&func.inputs),
+			status_constant(api, STATUS_CODES[0].0),
+		));
+	}
+	if let Some(comment) = string_ownership_comment(api, &func.output) {
+		binding.push_str(&comment);
+	}
+	if let Some(comment) = taken_ownership_comment(api, &func.inputs) {
+		binding.push_str(&comment);
+	}
+	binding.push_str(&format!(
+		"{} {}({});",
+		return_type_to_c(api, &func.output),
+		func.c_name(api),
+		c_param_list(&params),
+	));
+	binding
+}
+
+// Parameter list of a prototype; an empty `()` would leave the parameters
+// unspecified in C
+fn c_param_list(params: &[String]) -> String {
+	if params.is_empty() {
+		"void".to_string()
+	} else {
+		params.join(", ")
+	}
+}
+
+// Which opaque structs the call takes over, so C doesn't free them again
+fn taken_ownership_comment(api: &Api, params: &[Param]) -> Option<String> {
+	let taken = params
+		.iter()
+		.filter(|param| {
+			param.ownership == Ownership::Owned
+				&& takes_opaque_struct(api, &param.ty)
+		})
+		.map(|param| format!("`{}`", c_identifier(&param.name)))
+		.collect::<Vec<_>>();
+	if taken.is_empty() {
+		return None;
+	}
+	Some(format!("// Takes ownership of {}\n", taken.join(", ")))
+}
+
+// Whether C hands over an opaque struct's allocation by passing `ty`
+fn takes_opaque_struct(api: &Api, ty: &TypeRef) -> bool {
+	match ty {
+		TypeRef::Named(name) => api.is_opaque_struct(name),
+		TypeRef::Boxed(inner) | TypeRef::Option(inner) => {
+			takes_opaque_struct(api, inner)
+		}
+		_ => false,
+	}
+}
+
+// Who releases a string the function hands to C
+fn string_ownership_comment(api: &Api, output: &TypeRef) -> Option<String> {
+	let value = match output {
+		TypeRef::Result { ok, .. } => ok,
+		ty => ty,
+	};
+	let value = match value {
+		TypeRef::Option(inner) => inner,
+		ty => ty,
+	};
+	match value {
+		TypeRef::String | TypeRef::Str => Some(format!(
+			"// The returned string belongs to the caller, release it with {}\n",
+			string_free_name(api)
+		)),
+		TypeRef::StaticStr => Some(
+			"// The returned string is borrowed for the life of the program\n"
+				.to_string(),
+		),
+		_ => None,
+	}
+}
+
+// Name of the function releasing strings returned from Rust
+pub fn string_free_name(api: &Api) -> String {
+	format!("{}_string_free", api.crate_name.to_case(Case::Snake))
+}
+
+// The out-parameter a fallible function writes its result through
+fn out_param(api: &Api, inputs: &[Param], output: &TypeRef) -> Option<String> {
+	let value = output.out_value()?;
+	Some(format!(
+		"{}* {}",
+		return_type_to_c(api, value),
+		out_param_name(inputs)
+	))
+}
+
+// Name of the out-parameter, avoiding clashes with the function's own
+pub fn out_param_name(inputs: &[Param]) -> String {
+	let mut name = "out".to_string();
+	while inputs.iter().any(|param| param.name == name) {
+		name.push('_');
+	}
+	name
+}
+
+// Generate the status codes returned by fallible functions and the
+// functions reading the error the last failing call recorded
+fn generate_c_error_handling(api: &Api) -> String {
+	let codes = STATUS_CODES
+		.iter()
+		.enumerate()
+		.map(|(value, (code, description))| {
+			format!(
+				"    // {}\n    {} = {},",
+				description,
+				status_constant(api, code),
+				value
+			)
+		})
+		.collect::<Vec<_>>();
+
+	format!(
+		"typedef enum {name} {{\n{codes}\n}} {name};\n// The error recorded by the last failing call on this thread, or NULL; valid\n// until the next failing call or {prefix}_clear_last_error\nconst char* {prefix}_last_error_message(void);\n// Length in bytes of the last error message, 0 if there is none\nsize_t {prefix}_last_error_length(void);\nvoid {prefix}_clear_last_error(void);",
+		name = status_type(api),
+		prefix = api.crate_name.to_case(Case::Snake),
+		codes = codes.join("\n"),
+	)
+}
+
+// Name of the C status enum, e.g. `my_crate_status`
+fn status_type(api: &Api) -> String {
+	format!("{}_status", api.crate_name.to_case(Case::Snake))
+}
+
+// Name of a status code constant, e.g. `MY_CRATE_STATUS_OK`
+pub fn status_constant(api: &Api, code: &str) -> String {
+	format!(
+		"{}_{}",
+		status_type(api).to_case(Case::UpperSnake),
+		code.to_case(Case::UpperSnake)
+	)
+}
+
+// C parameters standing for a Rust parameter; slices, and strings under the
+// `length` UTF-8 policy, take a pointer and a length
+fn c_params(api: &Api, param: &Param, utf8: Utf8Policy) -> Vec<String> {
+	let name = c_identifier(&param.name);
+	let len = format!("size_t {}", slice_len_name(&param.name));
+	match &param.ty {
+		TypeRef::Slice { .. } => {
+			vec![format!("{} {}", rust_type_to_c(api, &param.ty), name), len]
+		}
+		ty if takes_length(ty, utf8) => {
+			vec![format!("const char* {}", name), len]
+		}
+		ty => vec![format!("{} {}", param_type_to_c(api, ty), name)],
+	}
+}
+
+// Name of the length parameter following a slice's pointer
+pub fn slice_len_name(param_name: &str) -> String {
+	format!("{}_len", param_name)
+}
+
+// Whether a string parameter is passed as a pointer and a byte length rather
+// than NUL-terminated
+pub fn takes_length(ty: &TypeRef, utf8: Utf8Policy) -> bool {
+	utf8 == Utf8Policy::Length && ty.is_string_from_c()
+}
+
+// Map parameter types to C types; trait objects passed to Rust are
+// implemented in C through callback tables
+pub fn param_type_to_c(api: &Api, ty: &TypeRef) -> String {
+	match (ty, ty.trait_object_name()) {
+		(TypeRef::Reference { .. }, Some(trait_name)) => {
+			format!("const {}_callbacks*", trait_name.to_case(Case::Snake))
+		}
+		(_, Some(trait_name)) => {
+			format!("{}_callbacks", trait_name.to_case(Case::Snake))
+		}
+		_ => rust_type_to_c(api, ty),
+	}
+}
+
+// Generate a struct of function pointers for a trait, plus the handle used
+// for boxed trait objects returned from Rust
+fn generate_c_trait_vtable(api: &Api, trait_item: &Trait) -> String {
+	let trait_name_c = trait_item.name.to_case(Case::Snake);
+
+	let entries = trait_item
+		.methods
+		.iter()
+		.map(|method| {
+			let receiver = match method.inputs[0].ty {
+				TypeRef::Reference { mutable: true, .. } => "void* self",
+				_ => "const void* self",
+			};
+			let params = std::iter::once(receiver.to_string())
+				.chain(method.inputs[1..].iter().flat_map(|param| {
+					c_params(api, param, api.utf8_policy(method))
+				}))
+				.chain(out_param(api, &method.inputs[1..], &method.output))
+				.collect::<Vec<_>>();
+			format!(
+				"    {} (*{})({});",
+				return_type_to_c(api, &method.output),
+				c_identifier(&method.name),
+				params.join(", ")
+			)
+		})
+		.collect::<Vec<_>>();
+
+	format!(
+		"typedef struct {name}_vtable {{\n{entries}\n    // Releases the object `self` points to\n    void (*free)(void* self);\n}} {name}_vtable;\n// A boxed `dyn {trait_name}`, used by passing `self` to the vtable's functions\ntypedef struct {name}_dyn {{\n    void* self;\n    const {name}_vtable* vtable;\n}} {name}_dyn;",
+		name = trait_name_c,
+		trait_name = trait_item.name,
+		entries = entries.join("\n"),
+	)
+}
+
+// Generate the callback table C code fills in to implement a trait
+fn generate_c_trait_callbacks(api: &Api, trait_item: &Trait) -> String {
+	let trait_name_c = trait_item.name.to_case(Case::Snake);
+
+	let entries = trait_item
+		.methods
+		.iter()
+		.map(|method| {
+			let params = std::iter::once("void* user_data".to_string())
+				.chain(method.inputs[1..].iter().map(|param| {
+					format!(
+						"{} {}",
+						callback_type_to_c(api, &param.ty),
+						c_identifier(&param.name)
+					)
+				}))
+				.collect::<Vec<_>>();
+			let comment = match method.output {
+				TypeRef::String => {
+					"    // The returned string is copied and stays owned by C\n"
+				}
+				_ => "",
+			};
+			format!(
+				"{}    {} (*{})({});",
+				comment,
+				callback_type_to_c(api, &method.output),
+				c_identifier(&method.name),
+				params.join(", ")
+			)
+		})
+		.collect::<Vec<_>>();
+
+	format!(
+		"// Implements `{trait_name}` in C: Rust calls each function with `user_data`,\n// possibly from any thread. Every function but `free` must be set, and strings\n// passed to them have interior NUL bytes escaped as `\\0`\ntypedef struct {name}_callbacks {{\n    void* user_data;\n{entries}\n    // Called once Rust drops an implementation it owns, may be NULL\n    void (*free)(void* user_data);\n}} {name}_callbacks;",
+		name = trait_name_c,
+		trait_name = trait_item.name,
+		entries = entries.join("\n"),
+	)
+}
+
+// Map types exchanged with C callbacks, which only borrow strings
+fn callback_type_to_c(api: &Api, ty: &TypeRef) -> String {
+	match ty {
+		TypeRef::String => "const char*".to_string(),
+		ty => rust_type_to_c(api, ty),
+	}
+}
+
+// Generate the buffer type holding a `Vec` and the function releasing it
+fn generate_c_vec_binding(api: &Api, element: &TypeRef) -> String {
+	format!(
+		"// A buffer owned by Rust; passing it to a function hands it back to Rust,\n// otherwise release it with {name}_free\ntypedef struct {name} {{\n    {element}* data;\n    size_t len;\n    size_t capacity;\n}} {name};\nvoid {name}_free({name}* vec);",
+		name = vec_type(api, element),
+		element = rust_type_to_c(api, element),
+	)
+}
+
+// Name of the buffer type for a `Vec`, e.g. `my_crate_vec_u8`
+pub fn vec_type(api: &Api, element: &TypeRef) -> String {
+	format!(
+		"{}_vec_{}",
+		api.crate_name.to_case(Case::Snake),
+		element.vec_element_name().unwrap_or_default()
+	)
+}
+
+// Generate the handle for an iterator returned from Rust, with the functions
+// advancing and releasing it
+fn generate_c_iterator_binding(api: &Api, item: &TypeRef) -> String {
+	format!(
+		"// A lazy sequence produced by Rust, release it with {name}_free\ntypedef struct {name} {name};\n{next}bool {name}_next({name}* iter, {item_c}* out);\nvoid {name}_free({name}* iter);",
+		name = iterator_type(api, item),
+		next = iterator_next_comment(api, item),
+		item_c = return_type_to_c(api, item),
+	)
+}
+
+// Describe `_next`, and who owns the items it writes out
+fn iterator_next_comment(api: &Api, item: &TypeRef) -> String {
+	let ownership = match item {
+		TypeRef::String => format!(
+			"; each string belongs to the caller, release it with {}",
+			string_free_name(api)
+		),
+		TypeRef::Named(name) if api.is_opaque_struct(name) => format!(
+			"; each item belongs to the caller, release it with {}_free",
+			api.c_type_name(name)
+		),
+		_ => String::new(),
+	};
+	format!(
+		"// Writes the next item to `out` and returns true, or returns false once the\n// sequence is exhausted or on error{}\n",
+		ownership
+	)
+}
+
+// Name of the handle type for an iterator, e.g. `my_crate_iterator_u32`
+pub fn iterator_type(api: &Api, item: &TypeRef) -> String {
+	format!(
+		"{}_iterator_{}",
+		api.crate_name.to_case(Case::Snake),
+		item.iterator_item_name(api).unwrap_or_default()
+	)
+}
+
+// Name of the struct for an `Option` with a presence flag, e.g.
+// `my_crate_option_u32`
+fn option_type(api: &Api, value: &TypeRef) -> String {
+	format!(
+		"{}_option_{}",
+		api.crate_name.to_case(Case::Snake),
+		value.option_value_name().unwrap_or_default()
+	)
+}
+
+// Generate the C struct along with its constructor and destructor
+fn generate_c_struct_binding(api: &Api, struct_item: &Struct) -> String {
+	let struct_name_c = api.c_type_name(&struct_item.name);
+
+	// `#[repr(C)]` structs share their layout with C and need no destructor
+	if struct_item.by_value {
+		let mut bindings = vec![c_struct_definition(
+			api,
+			&struct_name_c,
+			&struct_item.fields,
+		)];
+		bindings.extend(generate_c_constructor(api, struct_item));
+		bindings.extend(generate_c_standard_trait_functions(api, struct_item));
+		return bindings.join("\n");
+	}
+
+	// The handle itself is declared up front with every other one
+	let mut bindings = Vec::new();
+	bindings.extend(generate_c_constructor(api, struct_item));
+	bindings.push(format!(
+		"void {}_free({}* obj);",
+		struct_name_c, struct_name_c
+	));
+
+	bindings.extend(generate_c_standard_trait_functions(api, struct_item));
+
+	for (index, field) in api.accessor_fields(struct_item) {
+		bindings.extend(generate_c_accessors(api, struct_item, index, field));
+	}
+
+	bindings.join("\n")
+}
+
+// Generate prototypes calling the `Clone`, `PartialEq`, `Hash`, `Ord`,
+// `Display` and `Debug` implementations of a struct
+fn generate_c_standard_trait_functions(
+	api: &Api,
+	struct_item: &Struct,
+) -> Vec<String> {
+	let name = api.c_type_name(&struct_item.name);
+	api.standard_trait_functions(&struct_item.name)
+		.map(|(trait_name, suffix)| match trait_name {
+			"Clone" if struct_item.by_value => {
+				format!("{name} {name}_{suffix}(const {name}* obj);")
+			}
+			"Clone" => format!(
+				"// Returns a copy owned by the caller, release it with {name}_free\n{name}* {name}_{suffix}(const {name}* obj);"
+			),
+			"PartialEq" => {
+				format!("bool {name}_{suffix}(const {name}* a, const {name}* b);")
+			}
+			"Hash" => format!(
+				"// Equal values hash the same within one build of the library\nuint64_t {name}_{suffix}(const {name}* obj);"
+			),
+			"Display" | "Debug" => format!(
+				"{}char* {name}_{suffix}(const {name}* obj);",
+				string_ownership_comment(api, &TypeRef::String)
+					.unwrap_or_default()
+			),
+			_ => format!(
+				"// Negative, zero or positive as `a` orders before, equal to or after `b`\nint32_t {name}_{suffix}(const {name}* a, const {name}* b);"
+			),
+		})
+		.collect()
+}
+
+// Generate prototypes calling the `Display` and `Debug` implementations of
+// an enum, which is passed by pointer like a struct
+fn generate_c_enum_formatting_functions(
+	api: &Api,
+	enum_item: &Enum,
+) -> Vec<String> {
+	let name = api.c_type_name(&enum_item.name);
+	api.standard_trait_functions(&enum_item.name)
+		.map(|(_, suffix)| {
+			format!(
+				"{}char* {name}_{suffix}(const {name}* obj);",
+				string_ownership_comment(api, &TypeRef::String)
+					.unwrap_or_default()
+			)
+		})
+		.collect()
+}
+
+// Generate the `<struct>_new` prototype, unless a user-defined `new` takes
+// its place or C can't build the struct
+fn generate_c_constructor(api: &Api, struct_item: &Struct) -> Option<String> {
+	if api.has_method(&struct_item.name, "new") {
+		return None;
+	}
+	let params = match struct_item.constructor {
+		Constructor::Default => Vec::new(),
+		Constructor::Fields => struct_item.constructor_params(),
+		Constructor::None => return None,
+	};
+	Some(format!(
+		"{}{} {}_new({});",
+		taken_ownership_comment(api, &params).unwrap_or_default(),
+		rust_type_to_c(api, &TypeRef::Named(struct_item.name.clone())),
+		api.c_type_name(&struct_item.name),
+		c_param_list(
+			&params
+				.iter()
+				.flat_map(|param| c_params(api, param, api.utf8))
+				.collect::<Vec<_>>()
+		)
+	))
+}
+
+// Generate the getter and setter prototypes for a public field, unless the
+// struct has methods with the same names
+fn generate_c_accessors(
+	api: &Api,
+	struct_item: &Struct,
+	index: usize,
+	field: &Field,
+) -> Vec<String> {
+	let struct_name_c = api.c_type_name(&struct_item.name);
+	let suffix = field.accessor_suffix(index);
+	let mut accessors = Vec::new();
+
+	if !api.has_method(&struct_item.name, &format!("get_{}", suffix)) {
+		// Struct fields are lent out rather than copied
+		let (comment, ty) = match &field.ty {
+			TypeRef::Named(name) if api.find_struct(name).is_some() => (
+				"// Points into `obj`, valid until `obj` is freed or the field is set\n"
+					.to_string(),
+				format!("const {}*", api.c_type_name(name)),
+			),
+			ty => (
+				string_ownership_comment(api, ty).unwrap_or_default(),
+				return_type_to_c(api, ty),
+			),
+		};
+		accessors.push(format!(
+			"{comment}{ty} {struct_name_c}_get_{suffix}(const {struct_name_c}* obj);"
+		));
+	}
+
+	if !api.has_method(&struct_item.name, &format!("set_{}", suffix)) {
+		let value = Param::new("value".to_string(), field.ty.clone());
+		let comment = match &field.ty {
+			TypeRef::String => {
+				"// Copies `value`, which stays owned by the caller\n"
+					.to_string()
+			}
+			_ => taken_ownership_comment(api, std::slice::from_ref(&value))
+				.unwrap_or_default(),
+		};
+		let params = std::iter::once(format!("{}* obj", struct_name_c))
+			.chain(c_params(api, &value, api.utf8))
+			.collect::<Vec<_>>();
+		accessors.push(format!(
+			"{comment}void {struct_name_c}_set_{suffix}({});",
+			params.join(", ")
+		));
+	}
+
+	accessors
+}
+
+// Structs ordered so by-value structs come after the ones they embed
+fn structs_in_dependency_order(api: &Api) -> Vec<&Struct> {
+	fn visit<'a>(
+		api: &'a Api,
+		struct_item: &'a Struct,
+		ordered: &mut Vec<&'a Struct>,
+	) {
+		if ordered.iter().any(|s| s.name == struct_item.name) {
+			return;
+		}
+		for field in &struct_item.fields {
+			if let TypeRef::Named(name) = &field.ty {
+				if let Some(dependency) =
+					api.find_struct(name).filter(|s| s.by_value)
+				{
+					visit(api, dependency, ordered);
+				}
+			}
+		}
+		ordered.push(struct_item);
+	}
+
+	let mut ordered = Vec::new();
+	for struct_item in &api.structs {
+		visit(api, struct_item, &mut ordered);
+	}
+	ordered
+}
+
+// Generate a C struct typedef with one member per field
+fn c_struct_definition(
+	api: &Api,
+	struct_name_c: &str,
+	fields: &[Field],
+) -> String {
+	let fields = fields
+		.iter()
+		.enumerate()
+		.map(|(index, field)| {
+			format!(
+				"    {} {};",
+				rust_type_to_c(api, &field.ty),
+				identifier(&field.name_or_index(index))
+			)
+		})
+		.collect::<Vec<_>>();
+
+	format!(
+		"typedef struct {} {{\n{}\n}} {};",
+		struct_name_c,
+		fields.join("\n"),
+		struct_name_c
+	)
+}
+
+// Generate a tagged union for a data-carrying enum: a tag enum, one struct
+// per variant with fields and a struct holding the tag and a union of them
+fn generate_c_tagged_union_binding(api: &Api, enum_item: &Enum) -> String {
+	let enum_name_c = enum_item.name.to_case(Case::Snake);
+	let tag_name_c = format!("{}_tag", enum_name_c);
+
+	let tags = enum_item
+		.variants
+		.iter()
+		.map(|variant| {
+			format!(
+				"    {} = {},",
+				c_enum_constant(enum_item, &variant.name),
+				variant.discriminant
+			)
+		})
+		.collect::<Vec<_>>();
+	let mut bindings = vec![format!(
+		"typedef enum {} {{\n{}\n}} {};",
+		tag_name_c,
+		tags.join("\n"),
+		tag_name_c
+	)];
+
+	let mut members = Vec::new();
+	for variant in enum_item.variants.iter().filter(|v| !v.fields.is_empty()) {
+		let variant_name_c =
+			format!("{}_{}", enum_name_c, variant.name.to_case(Case::Snake));
+		bindings.push(c_struct_definition(
+			api,
+			&variant_name_c,
+			&variant.fields,
+		));
+		members.push(format!(
+			"        {} {};",
+			variant_name_c,
+			union_member_name(&variant.name)
+		));
+	}
+
+	bindings.push(format!(
+		"typedef struct {name} {{\n    {tag} tag;\n    union {{\n{members}\n    }} data;\n}} {name};",
+		name = enum_name_c,
+		tag = tag_name_c,
+		members = members.join("\n"),
+	));
+	bindings.push(format!(
+		"// Releases the strings owned by a value returned from Rust\nvoid {name}_free({name}* value);",
+		name = enum_name_c,
+	));
+
+	bindings.join("\n")
+}
+
+// Name of the union member holding a variant's fields
+pub fn union_member_name(variant_name: &str) -> String {
+	identifier(&variant_name.to_case(Case::Snake))
+}
+
+// Tagged unions embed each other by value, so dependencies must come first
+fn tagged_unions_in_dependency_order(api: &Api) -> Vec<&Enum> {
+	fn visit<'a>(
+		api: &'a Api,
+		enum_item: &'a Enum,
+		ordered: &mut Vec<&'a Enum>,
+	) {
+		if ordered.iter().any(|e| e.name == enum_item.name) {
+			return;
+		}
+		for field in enum_item.variants.iter().flat_map(|v| &v.fields) {
+			if let TypeRef::Named(name) = &field.ty {
+				if let Some(dependency) =
+					api.find_enum(name).filter(|e| !e.is_c_like())
+				{
+					visit(api, dependency, ordered);
+				}
+			}
+		}
+		ordered.push(enum_item);
+	}
+
+	let mut ordered = Vec::new();
+	for enum_item in api.enums.iter().filter(|e| !e.is_c_like()) {
+		visit(api, enum_item, &mut ordered);
+	}
+	ordered
+}
+
+// Generate a C enum with the Rust enum's discriminants
+fn generate_c_enum_binding(enum_item: &Enum) -> String {
+	let enum_name_c = enum_item.name.to_case(Case::Snake);
+
+	let variants = enum_item
+		.variants
+		.iter()
+		.map(|variant| {
+			format!(
+				"    {} = {},",
+				c_enum_constant(enum_item, &variant.name),
+				variant.discriminant
+			)
+		})
+		.collect::<Vec<_>>();
+
+	format!(
+		"typedef enum {} {{\n{}\n}} {};",
+		enum_name_c,
+		variants.join("\n"),
+		enum_name_c
+	)
+}
+
+// Name of the C constant for an enum variant, e.g. `MY_ENUM_VARIANT`
+pub fn c_enum_constant(enum_item: &Enum, variant_name: &str) -> String {
+	format!(
+		"{}_{}",
+		enum_item.name.to_case(Case::UpperSnake),
+		variant_name.to_case(Case::UpperSnake)
+	)
+}
+
+// Map returned types to C types; borrowed strings can't outlive the call, so
+// C gets an owned copy
+fn return_type_to_c(api: &Api, ty: &TypeRef) -> String {
+	match ty {
+		TypeRef::Str => "char*".to_string(),
+		TypeRef::Option(inner) if **inner == TypeRef::Str => {
+			"char*".to_string()
+		}
+		_ => rust_type_to_c(api, ty),
+	}
+}
+
+// Map model types to C types
+pub fn rust_type_to_c(api: &Api, ty: &TypeRef) -> String {
+	match ty {
+		TypeRef::Unit => "void".to_string(),
+		TypeRef::Primitive(primitive) => primitive_to_c(*primitive).to_string(),
+		TypeRef::Char => "uint32_t".to_string(),
+		TypeRef::String => "char*".to_string(),
+		TypeRef::Str | TypeRef::StaticStr => "const char*".to_string(),
+		TypeRef::Vec(inner) if inner.vec_element_name().is_some() => {
+			vec_type(api, inner)
+		}
+		TypeRef::Vec(_) => "void*".to_string(),
+		TypeRef::Option(inner) if inner.option_value_name().is_some() => {
+			option_type(api, inner)
+		}
+		// Other options are nullable pointers
+		TypeRef::Option(inner) => rust_type_to_c(api, inner),
+		TypeRef::Result { .. } => status_type(api),
+		// Enums are passed by value
+		TypeRef::Named(name) if api.find_enum(name).is_some() => {
+			api.c_type_name(name)
+		}
+		TypeRef::Named(name) if api.is_by_value_struct(name) => {
+			api.c_type_name(name)
+		}
+		TypeRef::Named(name) => format!("{}*", api.c_type_name(name)),
+		TypeRef::Boxed(inner) if matches!(&**inner, TypeRef::Named(name) if api.find_struct(name).is_some()) => {
+			rust_type_to_c(api, inner)
+		}
+		TypeRef::Boxed(inner) if matches!(&**inner, TypeRef::DynTrait(name) if api.find_trait(name).is_some()) =>
+		{
+			let TypeRef::DynTrait(trait_name) = &**inner else {
+				unreachable!()
+			};
+			format!("{}_dyn", trait_name.to_case(Case::Snake))
+		}
+		TypeRef::Boxed(_) | TypeRef::DynTrait(_) | TypeRef::ImplTrait(_) => {
+			"void*".to_string()
+		}
+		TypeRef::Slice { mutable, ty } => {
+			let constness = if *mutable { "" } else { "const " };
+			format!("{}{}*", constness, rust_type_to_c(api, ty))
+		}
+		TypeRef::Reference { mutable, ty } => {
+			let constness = if *mutable { "" } else { "const " };
+			match &**ty {
+				// Structs already cross the boundary as pointers, while
+				// enums are pointed to as their C representation
+				TypeRef::Named(name) => {
+					format!("{}{}*", constness, api.c_type_name(name))
+				}
+				TypeRef::Primitive(primitive) => {
+					format!("{}{}*", constness, primitive_to_c(*primitive))
+				}
+				_ => format!("{}void*", constness),
+			}
+		}
+		TypeRef::Iterator(item) => format!("{}*", iterator_type(api, item)),
+		TypeRef::Unsupported(_) => "void*".to_string(),
+	}
+}
+
+fn primitive_to_c(primitive: Primitive) -> &'static str {
+	match primitive {
+		// Fixed-width types match the Rust ABI exactly, unlike `int` or `long`
+		Primitive::Bool => "bool",
+		Primitive::I8 => "int8_t",
+		Primitive::I16 => "int16_t",
+		Primitive::I32 => "int32_t",
+		Primitive::I64 => "int64_t",
+		Primitive::Isize => "intptr_t",
+		Primitive::U8 => "uint8_t",
+		Primitive::U16 => "uint16_t",
+		Primitive::U32 => "uint32_t",
+		Primitive::U64 => "uint64_t",
+		Primitive::Usize => "size_t",
+		Primitive::F32 => "float",
+		Primitive::F64 => "double",
+	}
+}
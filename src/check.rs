@@ -0,0 +1,625 @@
+// Post-parse checks that drop items the emitters cannot represent
+
+use crate::model::{Api, Constructor, Function, TypeRef};
+use convert_case::{Case, Casing};
+
+// Remove unrepresentable items from the model, recording why
+pub fn check_api(api: &mut Api) {
+	mark_by_value_structs(api);
+	drop_unrepresentable_enums(api);
+	choose_constructors(api);
+	report_fields_without_accessors(api);
+	mark_c_implementable_traits(api);
+	drop_unsupported_traits(api);
+	drop_unsupported_iterator_items(api);
+	drop_clashing_methods(api);
+	drop_unsupported_functions(api);
+}
+
+// `#[repr(C)]` structs whose fields C can use in place are passed by value,
+// every other struct stays behind an opaque pointer
+fn mark_by_value_structs(api: &mut Api) {
+	// Embedding a struct requires that struct to be passed by value first
+	while let Some(index) = api.structs.iter().position(|struct_item| {
+		struct_item.repr_c
+			&& !struct_item.by_value
+			&& !struct_item.fields.is_empty()
+			&& struct_item
+				.fields
+				.iter()
+				.all(|field| is_by_value_field(api, &field.ty))
+	}) {
+		api.structs[index].by_value = true;
+	}
+
+	for struct_item in api.structs.iter().filter(|s| s.repr_c && !s.by_value) {
+		let reason = match struct_item
+			.fields
+			.iter()
+			.enumerate()
+			.find(|(_, field)| !is_by_value_field(api, &field.ty))
+		{
+			Some((index, field)) => format!(
+				"field `{}` has no C equivalent",
+				field.name_or_index(index)
+			),
+			None => "it has no fields".to_string(),
+		};
+		api.diagnostics.push(format!(
+			"passing `#[repr(C)]` struct `{}` as an opaque pointer: {}",
+			struct_item.name, reason
+		));
+	}
+}
+
+// Structs are built through `Default` when they implement it, or else from
+// their fields when C can pass every one of them
+fn choose_constructors(api: &mut Api) {
+	for index in 0..api.structs.len() {
+		let struct_item = &api.structs[index];
+		let field_reason =
+			struct_item
+				.fields
+				.iter()
+				.enumerate()
+				.find_map(|(index, field)| {
+					if !field.public {
+						Some(format!(
+							"field `{}` is private",
+							field.accessor_suffix(index)
+						))
+					} else if !api.has_accessors(&field.ty) {
+						Some(format!(
+							"field `{}` can't be passed from C",
+							field.accessor_suffix(index)
+						))
+					} else {
+						None
+					}
+				});
+
+		let constructor = if api.implements(&struct_item.name, "Default") {
+			Constructor::Default
+		} else if let Some(reason) = field_reason {
+			// A user-defined `new` takes the constructor's place anyway
+			if !api.has_method(&struct_item.name, "new") {
+				api.diagnostics.push(format!(
+					"not generating a constructor for `{}`: it doesn't implement `Default` and {}",
+					struct_item.name, reason
+				));
+			}
+			Constructor::None
+		} else {
+			Constructor::Fields
+		};
+		api.structs[index].constructor = constructor;
+	}
+}
+
+// Opaque structs expose their public fields through getters and setters
+fn report_fields_without_accessors(api: &mut Api) {
+	let mut diagnostics = Vec::new();
+	for struct_item in api.structs.iter().filter(|s| !s.by_value) {
+		for (index, field) in struct_item.fields.iter().enumerate() {
+			if field.public && !api.has_accessors(&field.ty) {
+				diagnostics.push(format!(
+					"not generating accessors for `{}::{}`: {:?} can't be copied to or from C",
+					struct_item.name,
+					field.accessor_suffix(index),
+					field.ty
+				));
+			}
+		}
+	}
+	api.diagnostics.extend(diagnostics);
+}
+
+fn is_by_value_field(api: &Api, ty: &TypeRef) -> bool {
+	match ty {
+		TypeRef::Primitive(_) => true,
+		TypeRef::Named(name) => api.is_by_value_struct(name),
+		_ => false,
+	}
+}
+
+// C code can implement a trait when every argument can be handed to a C
+// callback and every result read back from one
+fn mark_c_implementable_traits(api: &mut Api) {
+	for index in 0..api.traits.len() {
+		let trait_item = &api.traits[index];
+		let reason = trait_item.methods.iter().find_map(|method| {
+			if let Some(param) = method.inputs[1..]
+				.iter()
+				.find(|p| !is_callback_arg(api, &p.ty))
+			{
+				return Some(format!(
+					"`{}` can't be passed to C in `{}`",
+					param.name, method.name
+				));
+			}
+			(!is_callback_result(api, &method.output)).then(|| {
+				format!("the result of `{}` can't be read from C", method.name)
+			})
+		});
+
+		match reason {
+			Some(reason) => api.diagnostics.push(format!(
+				"not generating callbacks for trait `{}`: {}",
+				trait_item.name, reason
+			)),
+			None => api.traits[index].c_implementable = true,
+		}
+	}
+}
+
+// Vtables call every method through an extern "C" thunk, so a trait is only
+// exported when each method could be exported as a function
+fn drop_unsupported_traits(api: &mut Api) {
+	// Removing a trait can invalidate the methods taking it as `dyn Trait`
+	loop {
+		let rejected = api.traits.iter().find_map(|trait_item| {
+			trait_item.methods.iter().find_map(|method| {
+				// The receiver is the vtable's untyped `self`
+				let thunk = Function {
+					self_type: None,
+					inputs: method.inputs[1..].to_vec(),
+					..method.clone()
+				};
+				let reason = unsupported_function_reason(api, &thunk)?;
+				Some((trait_item.name.clone(), method.name.clone(), reason))
+			})
+		});
+
+		let Some((name, method_name, reason)) = rejected else {
+			break;
+		};
+		api.traits.retain(|trait_item| trait_item.name != name);
+		api.diagnostics.push(format!(
+			"skipping trait `{}` and its vtables: in `{}`, {}",
+			name, method_name, reason
+		));
+	}
+}
+
+fn is_callback_arg(api: &Api, ty: &TypeRef) -> bool {
+	match ty {
+		TypeRef::Primitive(_) | TypeRef::Str | TypeRef::String => true,
+		TypeRef::Named(name) => api.is_c_enum(name),
+		TypeRef::Reference { ty, .. } => match &**ty {
+			TypeRef::Primitive(_) => true,
+			TypeRef::Named(name) => api.find_struct(name).is_some(),
+			_ => false,
+		},
+		_ => false,
+	}
+}
+
+fn is_callback_result(api: &Api, ty: &TypeRef) -> bool {
+	match ty {
+		TypeRef::Unit | TypeRef::Primitive(_) | TypeRef::String => true,
+		TypeRef::Named(name) => api.is_c_enum(name),
+		_ => false,
+	}
+}
+
+// Data-carrying enums become tagged unions, so every payload field has to be
+// copyable into a C struct by value
+fn drop_unrepresentable_enums(api: &mut Api) {
+	// Removing an enum can invalidate the enums that embed it
+	loop {
+		let rejected = api.enums.iter().find_map(|enum_item| {
+			let field = enum_item
+				.variants
+				.iter()
+				.flat_map(|variant| &variant.fields)
+				.find(|field| !is_payload_type(api, &field.ty))?;
+			Some((enum_item.name.clone(), field.ty.clone()))
+		});
+
+		let Some((name, ty)) = rejected else {
+			break;
+		};
+		api.enums.retain(|enum_item| enum_item.name != name);
+		api.diagnostics.push(format!(
+			"skipping enum `{}`: payload type {:?} cannot be stored in a tagged union",
+			name, ty
+		));
+	}
+}
+
+fn is_payload_type(api: &Api, ty: &TypeRef) -> bool {
+	match ty {
+		TypeRef::Primitive(_) | TypeRef::String => true,
+		TypeRef::Named(name) => api.find_enum(name).is_some(),
+		_ => false,
+	}
+}
+
+// Structs implementing `Iterator` only get a `_next` function when C can take
+// their items
+fn drop_unsupported_iterator_items(api: &mut Api) {
+	for index in 0..api.trait_impls.len() {
+		let trait_impl = &api.trait_impls[index];
+		let Some(item) = &trait_impl.item else {
+			continue;
+		};
+		let exported = api.find_struct(&trait_impl.self_type).is_some()
+			|| api.find_enum(&trait_impl.self_type).is_some();
+		if !exported {
+			continue;
+		}
+		if !api.is_opaque_struct(&trait_impl.self_type) {
+			api.diagnostics.push(format!(
+				"not generating `{}_next`: only opaque structs can be iterated from C",
+				api.c_type_name(&trait_impl.self_type)
+			));
+		} else if !is_iterator_item(api, item) {
+			api.diagnostics.push(format!(
+				"not generating `{}_next`: items of type {:?} can't be handed to C",
+				api.c_type_name(&trait_impl.self_type),
+				item
+			));
+		} else {
+			continue;
+		}
+		api.trait_impls[index].item = None;
+	}
+}
+
+// Iterator items are written through an out-parameter like any returned value
+fn is_iterator_item(api: &Api, ty: &TypeRef) -> bool {
+	match ty {
+		TypeRef::Primitive(_) | TypeRef::String => true,
+		TypeRef::Named(name) => {
+			api.find_struct(name).is_some() || api.is_c_enum(name)
+		}
+		_ => false,
+	}
+}
+
+fn contains_iterator(ty: &TypeRef) -> bool {
+	match ty {
+		TypeRef::Iterator(_) => true,
+		TypeRef::Vec(inner)
+		| TypeRef::Option(inner)
+		| TypeRef::Boxed(inner)
+		| TypeRef::Reference { ty: inner, .. }
+		| TypeRef::Slice { ty: inner, .. } => contains_iterator(inner),
+		TypeRef::Result { ok, err } => {
+			contains_iterator(ok) || contains_iterator(err)
+		}
+		_ => false,
+	}
+}
+
+// Drop functions whose signature can't be bridged
+fn drop_unsupported_functions(api: &mut Api) {
+	let functions = std::mem::take(&mut api.functions);
+	for func in functions {
+		match unsupported_function_reason(api, &func) {
+			Some(reason) => api.diagnostics.push(format!(
+				"skipping function `{}`: {}",
+				func.c_name(api),
+				reason
+			)),
+			None => api.functions.push(func),
+		}
+	}
+}
+
+// Methods named like a function the wrappers generate for their type, such
+// as `free`, would export the same symbol twice
+fn drop_clashing_methods(api: &mut Api) {
+	let functions = std::mem::take(&mut api.functions);
+	for func in functions {
+		let clashes = func.self_type.as_ref().is_some_and(|self_type| {
+			generated_method_names(api, self_type)
+				.contains(&func.name.trim_start_matches("r#").to_string())
+		});
+		if clashes {
+			api.diagnostics.push(format!(
+				"skipping method `{}::{}`: `{}` is generated for the type itself",
+				func.self_type.as_deref().unwrap_or_default(),
+				func.name,
+				func.c_name(api)
+			));
+		} else {
+			api.functions.push(func);
+		}
+	}
+}
+
+// Suffixes of the functions generated for a type, which always take
+// precedence over its methods: `<type>_free` and the conversions of
+// enums, and the vtables of its trait implementations with their thunks
+fn generated_method_names(api: &Api, type_name: &str) -> Vec<String> {
+	let mut names = Vec::new();
+	if api.is_opaque_struct(type_name) {
+		names.push("free".to_string());
+	}
+	if let Some(enum_item) = api.find_enum(type_name) {
+		names.extend(["from_c".to_string(), "to_c".to_string()]);
+		if !enum_item.is_c_like() {
+			names.extend(["free".to_string(), "release".to_string()]);
+		}
+	}
+	for (trait_item, _) in api
+		.exported_trait_impls()
+		.filter(|(_, struct_item)| struct_item.name == type_name)
+	{
+		let prefix = trait_item.name.to_case(Case::Snake);
+		names.push(format!("{}_vtable", prefix));
+		names.push(format!("{}_free", prefix));
+		names.extend(trait_item.methods.iter().map(|method| {
+			format!("{}_{}", prefix, method.name.trim_start_matches("r#"))
+		}));
+	}
+	names
+}
+
+// Why a `Vec` or `Option` can't cross the boundary: `Vec`s become buffers
+// and `Option`s either nullable pointers or values with a presence flag
+fn unsupported_container(api: &Api, ty: &TypeRef) -> Option<String> {
+	match ty {
+		TypeRef::Vec(inner) if inner.vec_element_name().is_none() => {
+			Some(format!("`{:?}` can't be stored in a C buffer", inner))
+		}
+		TypeRef::Option(inner)
+			if inner.option_value_name().is_none()
+				&& !is_nullable_pointer(api, inner) =>
+		{
+			Some(format!("`Option<{:?}>` has no C representation", inner))
+		}
+		TypeRef::Result { ok: inner, .. } => unsupported_container(api, inner),
+		_ => None,
+	}
+}
+
+// Returned types the wrappers know how to hand over to C, once the more
+// specific checks have run
+fn is_c_result(api: &Api, ty: &TypeRef) -> bool {
+	match ty {
+		TypeRef::DynTrait(_)
+		| TypeRef::ImplTrait(_)
+		| TypeRef::Slice { .. }
+		| TypeRef::Unsupported(_) => false,
+		TypeRef::Option(inner) | TypeRef::Result { ok: inner, .. } => {
+			is_c_result(api, inner)
+		}
+		TypeRef::Boxed(inner) => match &**inner {
+			TypeRef::Named(name) => api.find_struct(name).is_some(),
+			inner => matches!(inner, TypeRef::DynTrait(_)),
+		},
+		TypeRef::Reference { ty, .. } => {
+			matches!(**ty, TypeRef::Named(_) | TypeRef::Primitive(_))
+		}
+		_ => true,
+	}
+}
+
+// Types C sees as a pointer, so `None` can be NULL
+pub fn is_nullable_pointer(api: &Api, ty: &TypeRef) -> bool {
+	match ty {
+		TypeRef::String | TypeRef::Str | TypeRef::StaticStr => true,
+		TypeRef::Named(name) => api.is_opaque_struct(name),
+		TypeRef::Boxed(inner) | TypeRef::Reference { ty: inner, .. } => {
+			match &**inner {
+				TypeRef::Named(name) if matches!(ty, TypeRef::Boxed(_)) => {
+					api.is_opaque_struct(name)
+				}
+				TypeRef::Named(name) => api.find_struct(name).is_some(),
+				TypeRef::Primitive(_) => {
+					matches!(ty, TypeRef::Reference { .. })
+				}
+				_ => false,
+			}
+		}
+		_ => false,
+	}
+}
+
+fn unsupported_function_reason(api: &Api, func: &Function) -> Option<String> {
+	// Methods are only exported for types that are exported themselves
+	if let Some(self_type) = &func.self_type {
+		if api.find_struct(self_type).is_none()
+			&& api.find_enum(self_type).is_none()
+		{
+			return Some(format!("`{}` is not an exported type", self_type));
+		}
+	}
+
+	// Only the crate's exported structs and enums have a C definition; errors
+	// are exempt, as they are only ever formatted
+	let output = match &func.output {
+		TypeRef::Result { ok, .. } => ok,
+		output => output,
+	};
+	if let Some(name) = func
+		.inputs
+		.iter()
+		.map(|param| &param.ty)
+		.chain([output])
+		.flat_map(TypeRef::nested_types)
+		.find_map(|ty| match ty {
+			TypeRef::Named(name)
+				if api.find_struct(name).is_none()
+					&& api.find_enum(name).is_none() =>
+			{
+				Some(name)
+			}
+			_ => None,
+		}) {
+		return Some(format!("`{}` is not an exported type", name));
+	}
+
+	// Trait objects coming from C are backed by callback tables
+	for param in &func.inputs {
+		if let Some(trait_name) = param.ty.trait_object_name() {
+			if !api
+				.find_trait(trait_name)
+				.is_some_and(|t| t.c_implementable)
+			{
+				return Some(format!(
+					"`{}` can't be implemented from C",
+					trait_name
+				));
+			}
+		}
+	}
+
+	// Trait objects returned to C must be boxed and use the trait's vtable
+	if let Some(trait_name) = func.output.trait_object_name() {
+		if !matches!(func.output, TypeRef::Boxed(_)) {
+			return Some(format!(
+				"only `Box<dyn {}>` can be returned to C",
+				trait_name
+			));
+		}
+		if api.find_trait(trait_name).is_none() {
+			return Some(format!("`{}` is not an exported trait", trait_name));
+		}
+	}
+
+	// Slices are rebuilt from C buffers, which only works for plain values
+	// Rust never hands back
+	for param in &func.inputs {
+		if let TypeRef::Slice { ty, .. } = &param.ty {
+			if !matches!(**ty, TypeRef::Primitive(_)) {
+				return Some(format!(
+					"`{}` is a slice of something other than primitives",
+					param.name
+				));
+			}
+		}
+	}
+	if matches!(func.output, TypeRef::Slice { .. }) {
+		return Some("slices can't be returned to C".to_string());
+	}
+
+	// Structs C passes by value have no allocation to hand over
+	if let Some(name) = func
+		.inputs
+		.iter()
+		.map(|param| &param.ty)
+		.chain([&func.output])
+		.find_map(|ty| match ty {
+			TypeRef::Boxed(inner) => match &**inner {
+				TypeRef::Named(name) if api.is_by_value_struct(name) => {
+					Some(name)
+				}
+				_ => None,
+			},
+			_ => None,
+		}) {
+		return Some(format!(
+			"`{}` is `#[repr(C)]`, so it is passed by value rather than boxed",
+			name
+		));
+	}
+
+	// Strings coming from C live no longer than the call
+	if let Some(param) = func.inputs.iter().find(|param| match &param.ty {
+		TypeRef::Option(inner) => **inner == TypeRef::StaticStr,
+		ty => *ty == TypeRef::StaticStr,
+	}) {
+		return Some(format!("`{}` takes a `&'static str`", param.name));
+	}
+
+	// Vecs and Options only hold what C can read directly
+	if let Some(reason) = func
+		.inputs
+		.iter()
+		.map(|param| &param.ty)
+		.chain([&func.output])
+		.find_map(|ty| unsupported_container(api, ty))
+	{
+		return Some(reason);
+	}
+
+	// Results become a status code, so they can only be returned, once
+	if let Some(param) = func
+		.inputs
+		.iter()
+		.find(|param| matches!(param.ty, TypeRef::Result { .. }))
+	{
+		return Some(format!("`{}` takes a `Result`", param.name));
+	}
+	if let TypeRef::Result { ok, .. } = &func.output {
+		if matches!(**ok, TypeRef::Result { .. }) {
+			return Some("nested `Result`s can't be returned to C".to_string());
+		}
+	}
+
+	// Types without a mapping, such as iterators borrowing from the call,
+	// have nothing C could hold on to
+	if let TypeRef::Unsupported(source) =
+		func.output.out_value().unwrap_or(&func.output)
+	{
+		return Some(format!("`{}` can't be returned to C", source));
+	}
+
+	// Everything else returned has to be something the wrappers can hand
+	// over, such as a boxed struct rather than a boxed enum
+	if !is_c_result(api, &func.output) {
+		return Some(format!("{:?} can't be returned to C", func.output));
+	}
+
+	// Iterators are boxed into a handle C pulls items from, so they can only
+	// be returned, directly or as the success value of a `Result`
+	if let Some(param) = func
+		.inputs
+		.iter()
+		.find(|param| contains_iterator(&param.ty))
+	{
+		return Some(format!("`{}` takes an iterator", param.name));
+	}
+	match func.output.out_value().unwrap_or(&func.output) {
+		TypeRef::Iterator(item) if !is_iterator_item(api, item) => {
+			return Some(format!(
+				"iterator items of type {:?} can't be handed to C",
+				item
+			));
+		}
+		TypeRef::Iterator(_) => {}
+		_ if contains_iterator(&func.output) => {
+			return Some(
+				"iterators can only be returned directly or in a `Result`"
+					.to_string(),
+			);
+		}
+		_ => {}
+	}
+
+	// Everything else C passes has to be something the wrappers can rebuild
+	// a Rust value from
+	if let Some(param) =
+		func.inputs.iter().find(|param| !api.is_c_param(&param.ty))
+	{
+		return Some(match &param.ty {
+			TypeRef::Unsupported(source) => {
+				format!(
+					"`{}` of type `{}` can't be passed from C",
+					param.name, source
+				)
+			}
+			ty => format!(
+				"`{}` of type {:?} can't be passed from C",
+				param.name, ty
+			),
+		});
+	}
+
+	// Tagged unions are copied across the boundary, so Rust can't hand back
+	// changes made through `&mut`
+	func.inputs.iter().find_map(|param| match &param.ty {
+		TypeRef::Reference { mutable: true, ty }
+			if matches!(&**ty, TypeRef::Named(name) if api.is_tagged_union(name)) =>
+		{
+			Some(format!(
+				"`{}` is a mutable reference to a tagged union",
+				param.name
+			))
+		}
+		_ => None,
+	})
+}
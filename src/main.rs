@@ -4,7 +4,7 @@ use std::{
 	path::PathBuf,
 };
 
-use cbt::generate_c_api_and_rust_exports;
+use cbt::{build_api, c_header, model::Utf8Policy, rust_wrapper};
 use clap::Parser;
 
 #[derive(Parser)]
@@ -17,6 +17,20 @@ struct Args {
 
 	#[clap(short, long)]
 	crate_name: String,
+
+	/// Also write the intermediate API model as JSON to this path
+	#[clap(long)]
+	model_json: Option<PathBuf>,
+
+	/// How string arguments are decoded: `reject` invalid UTF-8, replace it
+	/// (`lossy`), or take a byte `length` next to each string
+	#[clap(long, default_value = "reject")]
+	utf8: Utf8Policy,
+
+	/// Export a generic function or struct instantiated with these types,
+	/// e.g. `Matrix<f32>`; can be repeated
+	#[clap(long = "instantiate", value_name = "TYPE")]
+	instantiations: Vec<String>,
 }
 
 fn main() {
@@ -57,13 +71,27 @@ crate-type = ["cdylib", "staticlib"]
 
 	let input_file = fs::read_to_string(&args.input_file).unwrap();
 	let syntax_tree = syn::parse_file(&input_file).unwrap();
-	let (c_api, rust_exports) = generate_c_api_and_rust_exports(
+	let mut api = build_api(
 		&syntax_tree.items,
-		&args.input_file,
+		args.input_file.parent().unwrap(),
 		false,
 		&args.crate_name,
 		&args.crate_name,
+		&args.instantiations,
 	);
+	api.utf8 = args.utf8;
+
+	for diagnostic in &api.diagnostics {
+		eprintln!("warning: {}", diagnostic);
+	}
+
+	if let Some(model_json) = &args.model_json {
+		let model = serde_json::to_string_pretty(&api).unwrap();
+		fs::write(model_json, model).unwrap();
+	}
+
+	let c_api = c_header::generate(&api);
+	let rust_exports = rust_wrapper::generate(&api);
 
 	output_lib_rs.write_all(rust_exports.as_bytes()).unwrap();
 	output_c_header.write_all(c_api.as_bytes()).unwrap();
@@ -0,0 +1,822 @@
+// Intermediate API model shared by the syn parser and the code emitters
+
+use crate::naming::identifier;
+use convert_case::{Case, Casing};
+use serde::Serialize;
+
+// Everything cbt knows about a crate's public API
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Api {
+	pub crate_name: String,
+	pub structs: Vec<Struct>,
+	pub enums: Vec<Enum>,
+	pub functions: Vec<Function>,
+	pub traits: Vec<Trait>,
+	// `impl Trait for Type` blocks found anywhere in the crate
+	pub trait_impls: Vec<TraitImpl>,
+	// `type` aliases found anywhere in the crate, public or not, expanded
+	// wherever they're used
+	pub type_aliases: Vec<TypeAlias>,
+	// Items cbt skipped and why
+	pub diagnostics: Vec<String>,
+	// How strings from C are decoded, unless a function says otherwise
+	pub utf8: Utf8Policy,
+	// Instantiations of generic items requested outside the source, on top
+	// of their `/// cbt: instantiate` annotations
+	pub instantiations: Vec<Instance>,
+}
+
+impl Api {
+	// Look up a struct by its Rust name
+	pub fn find_struct(&self, name: &str) -> Option<&Struct> {
+		self.structs.iter().find(|s| s.name == name)
+	}
+
+	// Look up an enum by its Rust name
+	pub fn find_enum(&self, name: &str) -> Option<&Enum> {
+		self.enums.iter().find(|e| e.name == name)
+	}
+
+	// The alias `name` refers to in `module_path`: one declared in that
+	// module, or else the only public alias with that name
+	pub fn find_type_alias(
+		&self,
+		name: &str,
+		module_path: &[String],
+	) -> Option<&TypeAlias> {
+		let aliases =
+			self.type_aliases.iter().filter(|alias| alias.name == name);
+		if let Some(local) = aliases
+			.clone()
+			.find(|alias| alias.module_path == module_path)
+		{
+			return Some(local);
+		}
+		let mut public = aliases.filter(|alias| alias.public);
+		match (public.next(), public.next()) {
+			(Some(alias), None) => Some(alias),
+			_ => None,
+		}
+	}
+
+	// C name of a struct, enum or trait, e.g. `my_struct`, or `matrix_f32`
+	// for the struct instantiated as `Matrix<f32>`
+	pub fn c_type_name(&self, name: &str) -> String {
+		match self.find_struct(name).and_then(|s| s.instance.as_ref()) {
+			Some(instance) => instance.c_name(),
+			None => name.to_case(Case::Snake),
+		}
+	}
+
+	// Look up a trait by its Rust name
+	pub fn find_trait(&self, name: &str) -> Option<&Trait> {
+		self.traits.iter().find(|t| t.name == name)
+	}
+
+	// Implementations where both the trait and the struct are exported
+	pub fn exported_trait_impls(
+		&self,
+	) -> impl Iterator<Item = (&Trait, &Struct)> + '_ {
+		self.trait_impls.iter().filter_map(|trait_impl| {
+			Some((
+				self.find_trait(&trait_impl.trait_name)?,
+				self.find_struct(&trait_impl.self_type)?,
+			))
+		})
+	}
+
+	// Whether `type_name` implements a trait called `trait_name`
+	pub fn implements(&self, type_name: &str, trait_name: &str) -> bool {
+		self.trait_impls.iter().any(|trait_impl| {
+			trait_impl.self_type == type_name
+				&& trait_impl.trait_name == trait_name
+		})
+	}
+
+	// Whether `type_name` has a method or associated function called `name`
+	pub fn has_method(&self, type_name: &str, name: &str) -> bool {
+		self.functions.iter().any(|func| {
+			func.self_type.as_deref() == Some(type_name) && func.name == name
+		})
+	}
+
+	// Element types of the `Vec`s exchanged with C, each getting its own
+	// buffer type
+	pub fn vec_element_types(&self) -> Vec<&TypeRef> {
+		self.nested_types(|ty| match ty {
+			TypeRef::Vec(inner) if inner.vec_element_name().is_some() => {
+				Some(inner)
+			}
+			_ => None,
+		})
+	}
+
+	// Item types of the iterators returned to C, each getting its own handle
+	// type
+	pub fn iterator_item_types(&self) -> Vec<&TypeRef> {
+		self.nested_types(|ty| match ty {
+			TypeRef::Iterator(inner) => Some(inner),
+			_ => None,
+		})
+	}
+
+	// The `type Item` of a type's `Iterator` implementation
+	pub fn iterator_item(&self, type_name: &str) -> Option<&TypeRef> {
+		self.trait_impls
+			.iter()
+			.find(|trait_impl| {
+				trait_impl.self_type == type_name
+					&& trait_impl.trait_name == "Iterator"
+			})?
+			.item
+			.as_ref()
+	}
+
+	// Value types of the `Option`s passed to C with a presence flag, each
+	// getting its own struct
+	pub fn option_value_types(&self) -> Vec<&TypeRef> {
+		self.nested_types(|ty| match ty {
+			TypeRef::Option(inner) if inner.option_value_name().is_some() => {
+				Some(inner)
+			}
+			_ => None,
+		})
+	}
+
+	// Distinct types picked by `select` from every type, however deeply
+	// nested, in function and trait method signatures
+	fn nested_types<'a>(
+		&'a self,
+		select: impl Fn(&'a TypeRef) -> Option<&'a TypeRef>,
+	) -> Vec<&'a TypeRef> {
+		let mut types = Vec::new();
+		let signatures = self
+			.functions
+			.iter()
+			.chain(self.traits.iter().flat_map(|t| &t.methods));
+		for func in signatures {
+			for ty in func.inputs.iter().map(|p| &p.ty).chain([&func.output]) {
+				collect_nested_types(ty, &mut types);
+			}
+		}
+
+		let mut selected = Vec::new();
+		for ty in types.into_iter().filter_map(select) {
+			if !selected.contains(&ty) {
+				selected.push(ty);
+			}
+		}
+		selected
+	}
+
+	// The UTF-8 policy applying to a function's string arguments
+	pub fn utf8_policy(&self, func: &Function) -> Utf8Policy {
+		func.options.utf8.unwrap_or(self.utf8)
+	}
+
+	// Whether `name` is a `#[repr(C)]` struct C passes by value
+	pub fn is_by_value_struct(&self, name: &str) -> bool {
+		self.find_struct(name).is_some_and(|s| s.by_value)
+	}
+
+	// Whether `name` is a struct C only holds opaque pointers to
+	pub fn is_opaque_struct(&self, name: &str) -> bool {
+		self.find_struct(name).is_some_and(|s| !s.by_value)
+	}
+
+	// Public fields of an opaque struct that C reads and writes through
+	// accessor functions, with their positions
+	pub fn accessor_fields<'a>(
+		&'a self,
+		struct_item: &'a Struct,
+	) -> impl Iterator<Item = (usize, &'a Field)> + 'a {
+		struct_item
+			.fields
+			.iter()
+			.enumerate()
+			.filter(move |(_, field)| {
+				!struct_item.by_value
+					&& field.public && self.has_accessors(&field.ty)
+			})
+	}
+
+	// Whether a field of type `ty` can be read without moving it out and
+	// written from a C value
+	pub fn has_accessors(&self, ty: &TypeRef) -> bool {
+		let readable = match ty {
+			TypeRef::Primitive(_) | TypeRef::Char | TypeRef::String => true,
+			TypeRef::Named(name) => {
+				self.is_c_enum(name) || self.find_struct(name).is_some()
+			}
+			_ => false,
+		};
+		// Setters and field-wise constructors convert fields like parameters
+		readable && self.is_c_param(ty)
+	}
+
+	// Whether the wrappers know how to rebuild a Rust value of type `ty` from
+	// what C passes, for every generated function taking one: exported
+	// functions, vtable thunks, setters and constructors
+	pub fn is_c_param(&self, ty: &TypeRef) -> bool {
+		match ty {
+			TypeRef::Unit | TypeRef::DynTrait(_) | TypeRef::Unsupported(_) => {
+				false
+			}
+			TypeRef::Boxed(inner) => match &**inner {
+				TypeRef::Named(name) => self.find_struct(name).is_some(),
+				inner => matches!(inner, TypeRef::DynTrait(_)),
+			},
+			TypeRef::Reference { ty, .. } => matches!(
+				**ty,
+				TypeRef::Named(_)
+					| TypeRef::Primitive(_)
+					| TypeRef::DynTrait(_)
+			),
+			_ => true,
+		}
+	}
+
+	// Standard traits a struct or enum implements that get an exported
+	// function, with the function's suffix, unless the type has a method with
+	// that name; enums only get the formatting ones
+	pub fn standard_trait_functions<'a>(
+		&'a self,
+		type_name: &'a str,
+	) -> impl Iterator<Item = (&'static str, &'static str)> + 'a {
+		let formatting_only = self.find_enum(type_name).is_some();
+		STANDARD_TRAIT_FUNCTIONS.iter().copied().filter(
+			move |(trait_name, suffix)| {
+				(!formatting_only || matches!(*trait_name, "Display" | "Debug"))
+					&& self.implements(type_name, trait_name)
+					&& !self.has_method(type_name, suffix)
+			},
+		)
+	}
+
+	// Opaque structs implementing `Iterator`, with their item type, getting a
+	// generated `_next` unless they have a `next` method of their own
+	pub fn iterator_structs(&self) -> Vec<(&Struct, &TypeRef)> {
+		self.structs
+			.iter()
+			.filter(|s| !s.by_value && !self.has_method(&s.name, "next"))
+			.filter_map(|s| Some((s, self.iterator_item(&s.name)?)))
+			.collect()
+	}
+
+	// Whether `name` is a fieldless enum passed to C by value
+	pub fn is_c_enum(&self, name: &str) -> bool {
+		self.find_enum(name).is_some_and(Enum::is_c_like)
+	}
+
+	// Whether `name` is a data-carrying enum passed to C as a tagged union
+	pub fn is_tagged_union(&self, name: &str) -> bool {
+		self.find_enum(name).is_some_and(|e| !e.is_c_like())
+	}
+}
+
+// A free function, method or associated function exported to C
+#[derive(Debug, Clone, Serialize)]
+pub struct Function {
+	pub name: String,
+	pub module_path: Vec<String>,
+	// The type of the inherent `impl` block the function comes from
+	pub self_type: Option<String>,
+	// Methods take their receiver as a leading `self` parameter
+	pub inputs: Vec<Param>,
+	pub output: TypeRef,
+	// Whether the caller owns the returned value or borrows it from Rust
+	pub output_ownership: Ownership,
+	pub options: FunctionOptions,
+	// Set when the function is one instantiation of a generic function, in
+	// which case `name` is the instantiation's name
+	pub instance: Option<Instance>,
+}
+
+// Concrete type arguments for a generic item, e.g. `Matrix<f32>`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Instance {
+	pub generic_name: String,
+	// Rust source of each type argument
+	pub args: Vec<String>,
+}
+
+impl Instance {
+	// Name of the instantiation, e.g. `MatrixF32` for `Matrix<f32>` or
+	// `sum_f64` for `sum::<f64>`
+	pub fn name(&self) -> String {
+		if self.generic_name.starts_with(char::is_uppercase) {
+			let words = self.arg_words().map(|word| word.to_case(Case::Pascal));
+			std::iter::once(self.generic_name.clone())
+				.chain(words)
+				.collect()
+		} else {
+			self.c_name()
+		}
+	}
+
+	// C name of the instantiation, e.g. `matrix_f32` for `Matrix<f32>`, so
+	// struct and function instantiations are spelled alike
+	pub fn c_name(&self) -> String {
+		std::iter::once(self.generic_name.to_case(Case::Snake))
+			.chain(self.arg_words().map(|word| word.to_lowercase()))
+			.collect::<Vec<_>>()
+			.join("_")
+	}
+
+	// Words of every argument, e.g. `vec` and `u8` for `Vec<u8>`
+	fn arg_words(&self) -> impl Iterator<Item = &str> {
+		self.args
+			.iter()
+			.flat_map(|arg| arg.split(|c: char| !c.is_alphanumeric()))
+			.filter(|word| !word.is_empty())
+	}
+
+	// Rust name of the instantiation, e.g. `Matrix::<f32>`
+	pub fn rust_name(&self) -> String {
+		format!("{}::<{}>", self.generic_name, self.args.join(", "))
+	}
+}
+
+// Per-function settings read from `/// cbt: key = value` doc comments
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct FunctionOptions {
+	// Rust expression of the extern "C" return type handed to C when the
+	// function panics, instead of a status code or zero value
+	pub panic_value: Option<String>,
+	// Skip NULL checks on pointer arguments, for hot paths where the caller
+	// guarantees them
+	pub assume_nonnull: bool,
+	// Overrides the crate's UTF-8 policy for this function's string arguments
+	pub utf8: Option<Utf8Policy>,
+}
+
+// How string arguments coming from C are turned into Rust strings
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub enum Utf8Policy {
+	// Fail the call with an error status on invalid UTF-8
+	#[default]
+	Reject,
+	// Replace invalid sequences with U+FFFD
+	Lossy,
+	// Take a pointer and a byte length instead of a NUL-terminated string,
+	// rejecting invalid UTF-8
+	Length,
+}
+
+impl std::str::FromStr for Utf8Policy {
+	type Err = String;
+
+	fn from_str(name: &str) -> Result<Self, Self::Err> {
+		match name {
+			"reject" => Ok(Utf8Policy::Reject),
+			"lossy" => Ok(Utf8Policy::Lossy),
+			"length" => Ok(Utf8Policy::Length),
+			_ => Err(format!(
+				"unknown UTF-8 policy `{}`, expected `reject`, `lossy` or `length`",
+				name
+			)),
+		}
+	}
+}
+
+impl Function {
+	// Fully qualified Rust path of a free function, e.g. `my_crate::utils::add`
+	pub fn rust_path(&self) -> String {
+		rust_path(&self.module_path, &self.rust_name())
+	}
+
+	// Name of the function in Rust, with the type arguments of an
+	// instantiation
+	pub fn rust_name(&self) -> String {
+		match &self.instance {
+			Some(instance) => instance.rust_name(),
+			None => self.name.clone(),
+		}
+	}
+
+	// Name of the exported C function, prefixed with the type for methods;
+	// free functions can't be called after a C or Rust keyword
+	pub fn c_name(&self, api: &Api) -> String {
+		match &self.self_type {
+			Some(self_type) => format!(
+				"{}_{}",
+				api.c_type_name(self_type),
+				self.name.trim_start_matches("r#")
+			),
+			None => identifier(&self.name),
+		}
+	}
+
+	pub fn has_receiver(&self) -> bool {
+		self.inputs
+			.first()
+			.is_some_and(|param| param.name == "self")
+	}
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Param {
+	pub name: String,
+	pub ty: TypeRef,
+	// Whether the function takes the argument over or only borrows it
+	pub ownership: Ownership,
+}
+
+impl Param {
+	pub fn new(name: String, ty: TypeRef) -> Self {
+		Param {
+			name,
+			ownership: ty.ownership(),
+			ty,
+		}
+	}
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Struct {
+	pub name: String,
+	pub module_path: Vec<String>,
+	pub fields: Vec<Field>,
+	// Declared `#[repr(C)]`
+	pub repr_c: bool,
+	// Whether C sees the fields and passes the struct by value, rather than
+	// holding an opaque pointer
+	pub by_value: bool,
+	pub constructor: Constructor,
+	// Set when the struct is one instantiation of a generic struct, in which
+	// case `name` is the instantiation's name
+	pub instance: Option<Instance>,
+}
+
+impl Struct {
+	pub fn rust_path(&self) -> String {
+		match &self.instance {
+			Some(instance) => {
+				rust_path(&self.module_path, &instance.rust_name())
+			}
+			None => rust_path(&self.module_path, &self.name),
+		}
+	}
+
+	// Parameters of a field-wise constructor, one per field in order
+	pub fn constructor_params(&self) -> Vec<Param> {
+		self.fields
+			.iter()
+			.enumerate()
+			.map(|(index, field)| {
+				Param::new(
+					field
+						.name_or_index(index)
+						.trim_start_matches("r#")
+						.to_string(),
+					field.ty.clone(),
+				)
+			})
+			.collect()
+	}
+}
+
+// How `<struct>_new` builds a struct that has no `new` method of its own
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum Constructor {
+	// Through `Default`, without arguments
+	Default,
+	// From every field, which must all be public
+	Fields,
+	// C can't build the struct itself
+	None,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Field {
+	// `None` for tuple struct fields
+	pub name: Option<String>,
+	pub public: bool,
+	pub ty: TypeRef,
+}
+
+impl Field {
+	// Field name, or `_<index>` for tuple fields
+	pub fn name_or_index(&self, index: usize) -> String {
+		match &self.name {
+			Some(name) => name.clone(),
+			None => format!("_{}", index),
+		}
+	}
+
+	// Field name, or the bare index for tuple fields, as used in accessor
+	// names like `my_struct_get_x` and `my_struct_get_0`
+	pub fn accessor_suffix(&self, index: usize) -> String {
+		match &self.name {
+			Some(name) => name.trim_start_matches("r#").to_string(),
+			None => index.to_string(),
+		}
+	}
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Enum {
+	pub name: String,
+	pub module_path: Vec<String>,
+	pub variants: Vec<Variant>,
+}
+
+impl Enum {
+	pub fn rust_path(&self) -> String {
+		rust_path(&self.module_path, &self.name)
+	}
+
+	// Fieldless enums map directly onto C enums
+	pub fn is_c_like(&self) -> bool {
+		self.variants
+			.iter()
+			.all(|variant| variant.fields.is_empty())
+	}
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Variant {
+	pub name: String,
+	// Explicit or implicit discriminant value
+	pub discriminant: i64,
+	pub fields: Vec<Field>,
+}
+
+impl Variant {
+	// Whether the variant's fields are named (`A { x: i32 }`) rather than positional
+	pub fn has_named_fields(&self) -> bool {
+		self.fields.iter().any(|field| field.name.is_some())
+	}
+}
+
+// A trait whose methods all take `&self` or `&mut self`, so it can be used
+// as `dyn Trait`
+#[derive(Debug, Clone, Serialize)]
+pub struct Trait {
+	pub name: String,
+	pub module_path: Vec<String>,
+	// Methods take their receiver as a leading `self` parameter
+	pub methods: Vec<Function>,
+	// Whether C code can implement the trait through a callback table
+	pub c_implementable: bool,
+}
+
+impl Trait {
+	pub fn rust_path(&self) -> String {
+		rust_path(&self.module_path, &self.name)
+	}
+}
+
+// `type Name<T> = Target;`
+#[derive(Debug, Clone, Serialize)]
+pub struct TypeAlias {
+	pub name: String,
+	pub module_path: Vec<String>,
+	// Reachable from outside the crate
+	pub public: bool,
+	pub params: Vec<String>,
+	// Rust source of the aliased type, substituted for the alias when items
+	// are parsed
+	pub source: String,
+	// The aliased type, itself with aliases expanded
+	pub target: TypeRef,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TraitImpl {
+	pub trait_name: String,
+	pub self_type: String,
+	// `type Item` of an `Iterator` implementation
+	pub item: Option<TypeRef>,
+}
+
+// A Rust type as seen from the FFI boundary
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub enum TypeRef {
+	Unit,
+	Primitive(Primitive),
+	// `char`, passed as a `uint32_t` code point that Rust validates
+	Char,
+	// Owned `String`
+	String,
+	// Borrowed `&str`
+	Str,
+	// `&'static str`, which C can borrow for as long as the program runs
+	StaticStr,
+	Vec(Box<TypeRef>),
+	Option(Box<TypeRef>),
+	// `Result<T, E>`, returned to C as a status code with `T` written through
+	// an out-parameter
+	Result { ok: Box<TypeRef>, err: Box<TypeRef> },
+	// A struct or enum defined by the crate
+	Named(String),
+	// `Box<T>`
+	Boxed(Box<TypeRef>),
+	// The unsized `dyn Trait`, only found behind `Box` or a reference
+	DynTrait(String),
+	// `impl Trait` in argument position
+	ImplTrait(String),
+	Reference { mutable: bool, ty: Box<TypeRef> },
+	// `&[T]` or `&mut [T]`, passed from C as a pointer and a length
+	Slice { mutable: bool, ty: Box<TypeRef> },
+	// `impl Iterator<Item = T>` or `Box<dyn Iterator<Item = T>>`, handed to C
+	// as an opaque handle it pulls items from
+	Iterator(Box<TypeRef>),
+	// Anything cbt has no mapping for, kept as source text
+	Unsupported(String),
+}
+
+impl TypeRef {
+	// The trait behind `Box<dyn Trait>`, `&dyn Trait` or `impl Trait`
+	pub fn trait_object_name(&self) -> Option<&str> {
+		match self {
+			TypeRef::ImplTrait(name) => Some(name),
+			TypeRef::Boxed(inner) | TypeRef::Reference { ty: inner, .. } => {
+				match &**inner {
+					TypeRef::DynTrait(name) => Some(name),
+					_ => None,
+				}
+			}
+			_ => None,
+		}
+	}
+
+	// Name of a `Vec` element type in its buffer type's name, for the
+	// element types that can be handed to C
+	pub fn vec_element_name(&self) -> Option<&'static str> {
+		match self {
+			TypeRef::Primitive(primitive) => Some(primitive.rust_name()),
+			TypeRef::String => Some("string"),
+			_ => None,
+		}
+	}
+
+	// Name of an iterator's item type in its handle type's name
+	pub fn iterator_item_name(&self, api: &Api) -> Option<String> {
+		match self {
+			TypeRef::Primitive(primitive) => {
+				Some(primitive.rust_name().to_string())
+			}
+			TypeRef::String => Some("string".to_string()),
+			TypeRef::Named(name) => Some(api.c_type_name(name)),
+			_ => None,
+		}
+	}
+
+	// Name of an `Option` value type in its struct's name, for the value types
+	// passed with a presence flag rather than as a nullable pointer
+	pub fn option_value_name(&self) -> Option<&'static str> {
+		match self {
+			TypeRef::Primitive(primitive) => Some(primitive.rust_name()),
+			_ => None,
+		}
+	}
+
+	// Strings C hands to Rust, decoded according to the UTF-8 policy
+	pub fn is_string_from_c(&self) -> bool {
+		match self {
+			TypeRef::Option(inner) => {
+				matches!(**inner, TypeRef::String | TypeRef::Str)
+			}
+			ty => matches!(ty, TypeRef::String | TypeRef::Str),
+		}
+	}
+
+	// The value a fallible function writes through its out-parameter
+	pub fn out_value(&self) -> Option<&TypeRef> {
+		match self {
+			TypeRef::Result { ok, .. } if **ok != TypeRef::Unit => Some(ok),
+			_ => None,
+		}
+	}
+
+	// Who owns the value once it crosses the boundary
+	pub fn ownership(&self) -> Ownership {
+		match self {
+			TypeRef::Str
+			| TypeRef::StaticStr
+			| TypeRef::Reference { mutable: false, .. }
+			| TypeRef::Slice { mutable: false, .. } => Ownership::Borrowed,
+			TypeRef::Reference { mutable: true, .. }
+			| TypeRef::Slice { mutable: true, .. } => Ownership::BorrowedMut,
+			_ => Ownership::Owned,
+		}
+	}
+
+	// `self` and every type nested inside it
+	pub fn nested_types(&self) -> Vec<&TypeRef> {
+		let mut types = Vec::new();
+		collect_nested_types(self, &mut types);
+		types
+	}
+}
+
+// Standard traits whose methods C can call on a struct, e.g. to keep
+// objects in hash tables or sorted arrays or to log them, with the exported
+// function's suffix: `my_struct_clone`, `my_struct_equals`, ...
+pub const STANDARD_TRAIT_FUNCTIONS: &[(&str, &str)] = &[
+	("Clone", "clone"),
+	("PartialEq", "equals"),
+	("Hash", "hash"),
+	("Ord", "compare"),
+	("Display", "to_string"),
+	("Debug", "debug_string"),
+];
+
+// Status codes returned by fallible functions, numbered from 0, with the
+// situation each one reports
+pub const STATUS_CODES: &[(&str, &str)] = &[
+	("Ok", "The call succeeded"),
+	("Error", "The call failed, see the last error message"),
+	("Panic", "Rust panicked, see the last error message"),
+	("NullPointer", "A pointer argument was NULL"),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum Primitive {
+	Bool,
+	I8,
+	I16,
+	I32,
+	I64,
+	Isize,
+	U8,
+	U16,
+	U32,
+	U64,
+	Usize,
+	F32,
+	F64,
+}
+
+impl Primitive {
+	pub fn from_ident(ident: &str) -> Option<Self> {
+		match ident {
+			"bool" => Some(Primitive::Bool),
+			"i8" => Some(Primitive::I8),
+			"i16" => Some(Primitive::I16),
+			"i32" => Some(Primitive::I32),
+			"i64" => Some(Primitive::I64),
+			"isize" => Some(Primitive::Isize),
+			"u8" => Some(Primitive::U8),
+			"u16" => Some(Primitive::U16),
+			"u32" => Some(Primitive::U32),
+			"u64" => Some(Primitive::U64),
+			"usize" => Some(Primitive::Usize),
+			"f32" => Some(Primitive::F32),
+			"f64" => Some(Primitive::F64),
+			_ => None,
+		}
+	}
+
+	pub fn rust_name(self) -> &'static str {
+		match self {
+			Primitive::Bool => "bool",
+			Primitive::I8 => "i8",
+			Primitive::I16 => "i16",
+			Primitive::I32 => "i32",
+			Primitive::I64 => "i64",
+			Primitive::Isize => "isize",
+			Primitive::U8 => "u8",
+			Primitive::U16 => "u16",
+			Primitive::U32 => "u32",
+			Primitive::U64 => "u64",
+			Primitive::Usize => "usize",
+			Primitive::F32 => "f32",
+			Primitive::F64 => "f64",
+		}
+	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum Ownership {
+	Owned,
+	Borrowed,
+	BorrowedMut,
+}
+
+fn collect_nested_types<'a>(ty: &'a TypeRef, types: &mut Vec<&'a TypeRef>) {
+	types.push(ty);
+	match ty {
+		TypeRef::Vec(inner)
+		| TypeRef::Option(inner)
+		| TypeRef::Boxed(inner)
+		| TypeRef::Reference { ty: inner, .. }
+		| TypeRef::Slice { ty: inner, .. }
+		| TypeRef::Iterator(inner) => collect_nested_types(inner, types),
+		TypeRef::Result { ok, err } => {
+			collect_nested_types(ok, types);
+			collect_nested_types(err, types);
+		}
+		_ => {}
+	}
+}
+
+fn rust_path(module_path: &[String], name: &str) -> String {
+	module_path
+		.iter()
+		.map(String::as_str)
+		.chain(std::iter::once(name))
+		.collect::<Vec<_>>()
+		.join("::")
+}
@@ -0,0 +1,44 @@
+// Naming helpers shared by the emitters
+
+// Words that can't be used as identifiers in C
+const C_KEYWORDS: &[&str] = &[
+	"auto", "bool", "break", "case", "char", "const", "continue", "default",
+	"do", "double", "else", "enum", "extern", "false", "float", "for", "goto",
+	"if", "inline", "int", "long", "register", "restrict", "return", "short",
+	"signed", "sizeof", "static", "struct", "switch", "true", "typedef",
+	"union", "unsigned", "void", "volatile", "while",
+];
+
+// Words that can't be used as identifiers in C or Rust
+const RESERVED: &[&str] = &[
+	"as", "async", "auto", "await", "bool", "box", "break", "case", "char",
+	"const", "continue", "crate", "default", "do", "double", "dyn", "else",
+	"enum", "extern", "false", "float", "fn", "for", "goto", "if", "impl",
+	"in", "inline", "int", "let", "long", "loop", "match", "mod", "move",
+	"mut", "pub", "ref", "register", "restrict", "return", "self", "short",
+	"signed", "sizeof", "static", "struct", "super", "switch", "trait", "true",
+	"type", "typedef", "union", "unsafe", "unsigned", "use", "void",
+	"volatile", "where", "while",
+];
+
+// Append an underscore to identifiers that clash with a C keyword, dropping
+// the `r#` of raw identifiers
+pub fn c_identifier(name: &str) -> String {
+	let name = name.trim_start_matches("r#");
+	if C_KEYWORDS.contains(&name) {
+		format!("{}_", name)
+	} else {
+		name.to_string()
+	}
+}
+
+// Append an underscore to identifiers that clash with a C or Rust keyword,
+// dropping the `r#` of raw identifiers
+pub fn identifier(name: &str) -> String {
+	let name = name.trim_start_matches("r#");
+	if RESERVED.contains(&name) {
+		format!("{}_", name)
+	} else {
+		name.to_string()
+	}
+}
@@ -0,0 +1,2123 @@
+// Walk a crate's syntax tree (following external modules) and build the API model
+
+use crate::check::check_api;
+use crate::model::{
+	Api, Constructor, Enum, Field, Function, FunctionOptions, Instance, Param,
+	Primitive, Struct, Trait, TraitImpl, TypeAlias, TypeRef, Variant,
+};
+use convert_case::{Case, Casing};
+use proc_macro2::{Group, Span, TokenStream, TokenTree};
+use quote::{quote, ToTokens};
+use std::fs;
+use std::path::{Path, PathBuf};
+use syn::ext::IdentExt;
+use syn::parse::{Parse, Parser};
+use syn::punctuated::Punctuated;
+use syn::visit_mut::{self, VisitMut};
+use syn::{
+	Attribute, Expr, ExprLit, ExprUnary, Fields, FnArg, GenericArgument,
+	GenericParam, Generics, Ident, ImplItem, Item, ItemEnum, ItemImpl, ItemMod,
+	ItemStruct, ItemTrait, Lit, Meta, Pat, PatIdent, PatType, PathArguments,
+	PathSegment, ReturnType, Signature, Token, TraitBound, TraitItem, Type,
+	TypeParamBound, UnOp, UseTree, Visibility,
+};
+
+// Generic impl blocks, waiting for every instantiation of their self type to
+// be known, along with the module they're in
+type GenericImpls = Vec<(ItemImpl, Vec<String>)>;
+
+// Build the API model for a crate rooted at `syntax_tree`, instantiating
+// generic items with the types in `instantiations` as well as the ones they
+// are annotated with
+pub fn build_api(
+	syntax_tree: &[Item],
+	base_path: &Path,
+	parent_public: bool,
+	crate_name: &str,
+	mod_name: &str,
+	instantiations: &[String],
+) -> Api {
+	let mut api = collect_api(
+		syntax_tree,
+		base_path,
+		parent_public,
+		crate_name,
+		mod_name,
+		instantiations,
+	);
+	check_api(&mut api);
+	api
+}
+
+// The model as parsed, before anything C can't use is dropped from it
+fn collect_api(
+	syntax_tree: &[Item],
+	base_path: &Path,
+	parent_public: bool,
+	crate_name: &str,
+	mod_name: &str,
+	instantiations: &[String],
+) -> Api {
+	let mut api = Api {
+		crate_name: crate_name.to_case(Case::Snake),
+		..Default::default()
+	};
+	for source in instantiations {
+		match parse_instance(source) {
+			Ok(instance) => api.instantiations.push(instance),
+			Err(reason) => api.diagnostics.push(format!(
+				"ignoring instantiation `{}`: {}",
+				source, reason
+			)),
+		}
+	}
+	let module_path = vec![mod_name.to_case(Case::Snake)];
+	let mut scopes = Scopes::default();
+	collect_declarations(
+		syntax_tree,
+		base_path,
+		parent_public,
+		&module_path,
+		&mut api,
+		&mut scopes,
+	);
+	resolve_type_aliases(&mut api, &scopes);
+	let mut generic_impls = Vec::new();
+	collect_items(
+		syntax_tree,
+		base_path,
+		parent_public,
+		&module_path,
+		&mut api,
+		&scopes,
+		&mut generic_impls,
+	);
+	collect_generic_impls(generic_impls, &mut api);
+	api
+}
+
+// Read a configured instantiation such as `Matrix<f32>` or `sum::<f64>`
+fn parse_instance(source: &str) -> Result<Instance, String> {
+	let ty =
+		syn::parse_str::<Type>(source).map_err(|error| error.to_string())?;
+	let Type::Path(type_path) = &ty else {
+		return Err("expected a generic item's name and type arguments".into());
+	};
+	let segment = type_path.path.segments.last().unwrap();
+	let args = generic_type_args(&segment.arguments)
+		.ok_or_else(|| format!("`{}` has no type arguments", segment.ident))?;
+	Ok(Instance {
+		generic_name: segment.ident.to_string(),
+		args,
+	})
+}
+
+// Recursively add the public items of a module to the model
+fn collect_items(
+	syntax_tree: &[Item],
+	base_path: &Path,
+	parent_public: bool,
+	module_path: &[String],
+	api: &mut Api,
+	scopes: &Scopes,
+	generic_impls: &mut GenericImpls,
+) {
+	for item in syntax_tree {
+		match &resolve_types(item, module_path, api, scopes) {
+			Item::Fn(func) if is_public(&func.vis, parent_public) => {
+				collect_function(
+					&func.attrs,
+					&func.sig,
+					module_path,
+					None,
+					api,
+				);
+			}
+			Item::Struct(struct_item)
+				if is_public(&struct_item.vis, parent_public)
+					&& !is_shadowed(
+						&struct_item.ident,
+						module_path,
+						scopes,
+						api,
+					) =>
+			{
+				collect_struct(struct_item, module_path, api);
+			}
+			Item::Enum(enum_item)
+				if is_public(&enum_item.vis, parent_public)
+					&& !is_shadowed(
+						&enum_item.ident,
+						module_path,
+						scopes,
+						api,
+					) =>
+			{
+				match parse_enum(enum_item, module_path) {
+					Ok(enum_model) => {
+						api.enums.push(enum_model);
+						collect_derives(
+							&enum_item.attrs,
+							&enum_item.ident.to_string(),
+							api,
+						);
+					}
+					Err(reason) => api.diagnostics.push(format!(
+						"skipping enum `{}`: {}",
+						enum_item.ident, reason
+					)),
+				}
+			}
+			Item::Impl(item_impl)
+				if type_params(&item_impl.generics)
+					.map_or(true, |params| !params.is_empty()) =>
+			{
+				generic_impls.push((item_impl.clone(), module_path.to_vec()));
+			}
+			Item::Impl(item_impl) if item_impl.trait_.is_none() => {
+				collect_inherent_impl(item_impl, module_path, api);
+			}
+			Item::Impl(item_impl) => collect_trait_impl(item_impl, api),
+			Item::Trait(item_trait)
+				if is_public(&item_trait.vis, parent_public)
+					&& !is_shadowed(
+						&item_trait.ident,
+						module_path,
+						scopes,
+						api,
+					) =>
+			{
+				match parse_trait(item_trait, module_path) {
+					Ok(trait_model) => api.traits.push(trait_model),
+					Err(reason) => api.diagnostics.push(format!(
+						"skipping trait `{}`: {}",
+						item_trait.ident, reason
+					)),
+				}
+			}
+			Item::Mod(module) if is_public_mod(module) => {
+				let mut module_path = module_path.to_vec();
+				module_path.push(module.ident.to_string());
+
+				if let Some((_, items)) = &module.content {
+					// Inline module: recurse into the module's items
+					collect_items(
+						items,
+						base_path,
+						true,
+						&module_path,
+						api,
+						scopes,
+						generic_impls,
+					);
+				} else {
+					// External module: read the corresponding file and recurse
+					process_external_mod(
+						module,
+						base_path,
+						&module_path,
+						api,
+						scopes,
+						generic_impls,
+					);
+				}
+			}
+			_ => {}
+		}
+	}
+}
+
+// Whether an earlier exported type has the name of the one declared in
+// `module_path`, recording that it is skipped
+fn is_shadowed(
+	ident: &Ident,
+	module_path: &[String],
+	scopes: &Scopes,
+	api: &mut Api,
+) -> bool {
+	let name = ident.to_string();
+	let Some(exported) = scopes.exported_type(&name) else {
+		return false;
+	};
+	if exported[..exported.len() - 1] == *module_path {
+		return false;
+	}
+	api.diagnostics.push(format!(
+		"skipping `{}::{}`: `{}` is exported under the same name",
+		module_path.join("::"),
+		name,
+		exported.join("::")
+	));
+	true
+}
+
+// An item is considered public if it's declared `pub` or lives inside a public module
+fn is_public(vis: &Visibility, parent_public: bool) -> bool {
+	match vis {
+		Visibility::Public(_) => true,
+		_ => parent_public,
+	}
+}
+
+// Check if the module is public
+fn is_public_mod(module: &ItemMod) -> bool {
+	matches!(&module.vis, Visibility::Public(_))
+}
+
+// Process an external module by reading its corresponding file
+fn process_external_mod(
+	module: &ItemMod,
+	base_path: &Path,
+	module_path: &[String],
+	api: &mut Api,
+	scopes: &Scopes,
+	generic_impls: &mut GenericImpls,
+) {
+	let Some((items, mod_base_path)) = read_external_mod(module, base_path)
+	else {
+		return;
+	};
+	collect_items(
+		&items,
+		&mod_base_path,
+		true,
+		module_path,
+		api,
+		scopes,
+		generic_impls,
+	);
+}
+
+// Items of an external module, along with the directory its own external
+// modules are found in
+fn read_external_mod(
+	module: &ItemMod,
+	base_path: &Path,
+) -> Option<(Vec<Item>, PathBuf)> {
+	let module_name = module.ident.to_string();
+
+	// Try both 'mod.rs' and '<module>.rs' patterns
+	let mod_file_path = base_path.join(format!("{}.rs", module_name));
+	let mod_folder_path = base_path.join(format!("{}/mod.rs", module_name));
+
+	let mod_path = if mod_file_path.exists() {
+		mod_file_path
+	} else if mod_folder_path.exists() {
+		mod_folder_path
+	} else {
+		return None; // If neither path exists, there's nothing to add
+	};
+
+	// Read the module file content
+	let mod_source =
+		fs::read_to_string(&mod_path).expect("Unable to read module file");
+
+	// Parse the module file content
+	let mod_syntax_tree =
+		syn::parse_file(&mod_source).expect("Unable to parse module file");
+
+	Some((
+		mod_syntax_tree.items,
+		mod_path.parent().unwrap().to_path_buf(),
+	))
+}
+
+// Names `use` declarations bring into each module, and the modules paths can
+// go through, so a type is recognised however it is spelled
+#[derive(Default)]
+struct Scopes {
+	// Module, the name bound in it, and the path that name stands for
+	imports: Vec<(Vec<String>, String, Vec<String>)>,
+	// Module, and a module whose items it imports with `*`
+	globs: Vec<(Vec<String>, Vec<String>)>,
+	modules: Vec<Vec<String>>,
+	// Full path of every struct, enum and trait, and whether it's exported
+	types: Vec<(Vec<String>, bool)>,
+}
+
+impl Scopes {
+	// Whether a struct, enum or trait is declared at `path`
+	fn declares(&self, path: &[String]) -> bool {
+		self.types.iter().any(|(declared, _)| declared == path)
+	}
+
+	// The exported type the model knows as `name`: the first one declared,
+	// since C has a single namespace for them
+	fn exported_type(&self, name: &str) -> Option<&[String]> {
+		self.types
+			.iter()
+			.filter(|(_, exported)| *exported)
+			.map(|(path, _)| path.as_slice())
+			.find(|path| path.last().is_some_and(|last| last == name))
+	}
+
+	// Record what a `use` tree binds in `module_path`
+	fn add_use(
+		&mut self,
+		tree: &UseTree,
+		prefix: &[String],
+		module_path: &[String],
+	) {
+		let mut path = prefix.to_vec();
+		match tree {
+			UseTree::Path(use_path) => {
+				path.push(use_path.ident.to_string());
+				self.add_use(&use_path.tree, &path, module_path);
+			}
+			UseTree::Name(name) if name.ident == "self" => {
+				if let Some(last) = path.last() {
+					self.imports.push((
+						module_path.to_vec(),
+						last.clone(),
+						path.clone(),
+					));
+				}
+			}
+			UseTree::Name(name) => {
+				path.push(name.ident.to_string());
+				self.imports.push((
+					module_path.to_vec(),
+					name.ident.to_string(),
+					path,
+				));
+			}
+			UseTree::Rename(rename) if rename.rename != "_" => {
+				if rename.ident != "self" {
+					path.push(rename.ident.to_string());
+				}
+				self.imports.push((
+					module_path.to_vec(),
+					rename.rename.to_string(),
+					path,
+				));
+			}
+			UseTree::Rename(_) => {}
+			UseTree::Glob(_) => {
+				self.globs.push((module_path.to_vec(), path));
+			}
+			UseTree::Group(group) => {
+				for tree in &group.items {
+					self.add_use(tree, prefix, module_path);
+				}
+			}
+		}
+	}
+
+	// Full path of what `path` names when spelled in `module_path`, starting
+	// with the crate's root module for items of the crate
+	fn resolve(&self, path: &[String], module_path: &[String]) -> Vec<String> {
+		self.resolve_in(path, module_path, 0)
+	}
+
+	fn resolve_in(
+		&self,
+		path: &[String],
+		module_path: &[String],
+		depth: usize,
+	) -> Vec<String> {
+		let Some((first, rest)) = path.split_first() else {
+			return module_path.to_vec();
+		};
+		if depth > 16 {
+			return path.to_vec();
+		}
+		let within = |base: &[String]| {
+			base.iter().chain(rest).cloned().collect::<Vec<_>>()
+		};
+		match first.as_str() {
+			"crate" => self.follow(within(&module_path[..1]), depth),
+			"self" => self.follow(within(module_path), depth),
+			"super" => self.resolve_in(
+				rest,
+				&module_path[..module_path.len().saturating_sub(1).max(1)],
+				depth + 1,
+			),
+			_ => {
+				if let Some((_, _, target)) =
+					self.imports.iter().find(|(module, name, _)| {
+						module == module_path && name == first
+					}) {
+					let target: Vec<_> =
+						target.iter().chain(rest).cloned().collect();
+					return self.resolve_in(&target, module_path, depth + 1);
+				}
+				let mut child = module_path.to_vec();
+				child.push(first.clone());
+				if rest.is_empty() || self.modules.contains(&child) {
+					self.follow(
+						module_path.iter().chain(path).cloned().collect(),
+						depth,
+					)
+				} else {
+					// A path into another crate
+					path.to_vec()
+				}
+			}
+		}
+	}
+
+	// Chase a path naming something re-exported with `use`
+	fn follow(&self, path: Vec<String>, depth: usize) -> Vec<String> {
+		let Some((name, module_path)) = path.split_last() else {
+			return path;
+		};
+		match self.imports.iter().find(|(module, imported, _)| {
+			module == module_path && imported == name
+		}) {
+			Some((_, _, target)) => {
+				self.resolve_in(target, module_path, depth + 1)
+			}
+			None => path,
+		}
+	}
+}
+
+// Record the `type` aliases and `use` declarations of a module and its public
+// submodules, ahead of parsing the items that use them
+fn collect_declarations(
+	syntax_tree: &[Item],
+	base_path: &Path,
+	parent_public: bool,
+	module_path: &[String],
+	api: &mut Api,
+	scopes: &mut Scopes,
+) {
+	scopes.modules.push(module_path.to_vec());
+	for item in syntax_tree {
+		match item {
+			Item::Type(alias) => {
+				// Const generic aliases are left for the compiler to resolve
+				let Ok(params) = type_params(&alias.generics) else {
+					continue;
+				};
+				let target = &alias.ty;
+				api.type_aliases.push(TypeAlias {
+					name: alias.ident.to_string(),
+					module_path: module_path.to_vec(),
+					public: is_public(&alias.vis, parent_public),
+					params: params.iter().map(Ident::to_string).collect(),
+					source: quote! { #target }.to_string(),
+					target: TypeRef::Unit,
+				});
+			}
+			Item::Use(item_use) if item_use.leading_colon.is_none() => {
+				scopes.add_use(&item_use.tree, &[], module_path);
+			}
+			Item::Struct(ItemStruct { ident, vis, .. })
+			| Item::Enum(ItemEnum { ident, vis, .. })
+			| Item::Trait(ItemTrait { ident, vis, .. }) => {
+				let mut path = module_path.to_vec();
+				path.push(ident.to_string());
+				scopes.types.push((path, is_public(vis, parent_public)));
+			}
+			Item::Mod(module) => {
+				let mut module_path = module_path.to_vec();
+				module_path.push(module.ident.to_string());
+				// Private modules are walked too, as paths can still lead
+				// through them
+				let public = is_public_mod(module);
+				let first_alias = api.type_aliases.len();
+				let first_type = scopes.types.len();
+				if let Some((_, items)) = &module.content {
+					collect_declarations(
+						items,
+						base_path,
+						public,
+						&module_path,
+						api,
+						scopes,
+					);
+				} else if let Some((items, mod_base_path)) =
+					read_external_mod(module, base_path)
+				{
+					collect_declarations(
+						&items,
+						&mod_base_path,
+						public,
+						&module_path,
+						api,
+						scopes,
+					);
+				}
+				if !public {
+					for alias in &mut api.type_aliases[first_alias..] {
+						alias.public = false;
+					}
+					for (_, exported) in &mut scopes.types[first_type..] {
+						*exported = false;
+					}
+				}
+			}
+			_ => {}
+		}
+	}
+}
+
+// Work out what each alias finally stands for, once they are all known
+fn resolve_type_aliases(api: &mut Api, scopes: &Scopes) {
+	let targets = api
+		.type_aliases
+		.iter()
+		.map(|alias| match syn::parse_str::<Type>(&alias.source) {
+			Ok(mut ty) => {
+				TypeResolver::new(api, scopes, &alias.module_path)
+					.visit_type_mut(&mut ty);
+				parse_type(&ty)
+			}
+			Err(_) => TypeRef::Unsupported(alias.source.clone()),
+		})
+		.collect::<Vec<_>>();
+	for (alias, target) in api.type_aliases.iter_mut().zip(targets) {
+		alias.target = target;
+	}
+}
+
+// Copy of an item with the type aliases it uses replaced by their targets,
+// and the paths naming the crate's own items cut down to those items' names
+fn resolve_types(
+	item: &Item,
+	module_path: &[String],
+	api: &Api,
+	scopes: &Scopes,
+) -> Item {
+	let mut item = item.clone();
+	TypeResolver::new(api, scopes, module_path).visit_item_mut(&mut item);
+	item
+}
+
+// Rewrites the types and trait paths of items in `module_path`
+struct TypeResolver<'a> {
+	api: &'a Api,
+	scopes: &'a Scopes,
+	module_path: &'a [String],
+	// How many aliases deep the expansion is, to give up on cycles
+	depth: usize,
+}
+
+impl<'a> TypeResolver<'a> {
+	fn new(
+		api: &'a Api,
+		scopes: &'a Scopes,
+		module_path: &'a [String],
+	) -> Self {
+		TypeResolver {
+			api,
+			scopes,
+			module_path,
+			depth: 0,
+		}
+	}
+
+	// The alias a path like `Res` or `crate::ids::UserId` names
+	fn find_alias(&self, path: &syn::Path) -> Option<&'a TypeAlias> {
+		let spelled = path_names(path);
+		let resolved = self.scopes.resolve(&spelled, self.module_path);
+		let (name, module_path) = resolved.split_last()?;
+		let declared_in = |module_path: &[String]| {
+			self.api.type_aliases.iter().find(|alias| {
+				alias.name == *name && alias.module_path == module_path
+			})
+		};
+		if let Some(alias) = declared_in(module_path) {
+			return Some(alias);
+		}
+		let glob_alias = self
+			.scopes
+			.globs
+			.iter()
+			.filter(|(module, _)| module == module_path)
+			.find_map(|(_, glob)| {
+				declared_in(&self.scopes.resolve(glob, module_path))
+			});
+		// A name brought in by `use` is known to be something else
+		match glob_alias {
+			Some(alias) => Some(alias),
+			None if self.is_local_name(&spelled, &resolved) => {
+				self.api.find_type_alias(name, self.module_path)
+			}
+			None => None,
+		}
+	}
+
+	// The type an alias use like `Res<u32>` stands for
+	fn expand(&self, ty: &Type) -> Option<Type> {
+		let Type::Path(type_path) = ty else {
+			return None;
+		};
+		if type_path.qself.is_some() {
+			return None;
+		}
+		let alias = self.find_alias(&type_path.path)?;
+		let segment = type_path.path.segments.last()?;
+		let args = match &segment.arguments {
+			PathArguments::AngleBracketed(args) => args
+				.args
+				.iter()
+				.filter_map(|arg| match arg {
+					GenericArgument::Type(ty) => Some(ty.clone()),
+					_ => None,
+				})
+				.collect(),
+			_ => Vec::new(),
+		};
+		if args.len() != alias.params.len() || self.depth > 16 {
+			return None;
+		}
+
+		// The target names types as seen from the alias's own module
+		let mut target = syn::parse_str::<Type>(&alias.source).ok()?;
+		TypeResolver {
+			api: self.api,
+			scopes: self.scopes,
+			module_path: &alias.module_path,
+			depth: self.depth + 1,
+		}
+		.visit_type_mut(&mut target);
+		let params = alias
+			.params
+			.iter()
+			.map(|param| Ident::new(param, Span::call_site()))
+			.collect::<Vec<_>>();
+		substitute(&target, &params, &args).ok()
+	}
+
+	// Whether a single name wasn't brought in by `use`, so it may be a type
+	// parameter, `Self` or something from the prelude
+	fn is_local_name(&self, spelled: &[String], resolved: &[String]) -> bool {
+		spelled.len() == 1
+			&& resolved.len() == self.module_path.len() + 1
+			&& resolved.starts_with(self.module_path)
+	}
+
+	// Spell a path the way the model names what it leads to: just the name
+	// for the crate's exported types, and the full path for anything else,
+	// which `parse_type` only maps for a few standard library types
+	fn canonicalize(&self, path: &mut syn::Path) {
+		let spelled = path_names(path);
+		let mut resolved = self.scopes.resolve(&spelled, self.module_path);
+		if self.is_local_name(&spelled, &resolved)
+			&& !self.scopes.declares(&resolved)
+		{
+			// Glob imports are only looked into when nothing closer matches
+			let glob_type = self
+				.scopes
+				.globs
+				.iter()
+				.filter(|(module, _)| module == self.module_path)
+				.map(|(_, glob)| {
+					let mut path = self.scopes.resolve(glob, self.module_path);
+					path.extend(spelled.iter().cloned());
+					path
+				})
+				.find(|path| self.scopes.declares(path));
+			match glob_type {
+				Some(glob_type) => resolved = glob_type,
+				None => return,
+			}
+		}
+		let name = &resolved[resolved.len() - 1..];
+		let canonical = match self.scopes.exported_type(&name[0]) {
+			Some(exported) if exported == resolved => name,
+			_ => &resolved[..],
+		};
+		if canonical == spelled {
+			return;
+		}
+		let arguments = path.segments.last().unwrap().arguments.clone();
+		path.leading_colon = None;
+		path.segments = canonical
+			.iter()
+			.map(|name| PathSegment::from(Ident::new(name, Span::call_site())))
+			.collect();
+		path.segments.last_mut().unwrap().arguments = arguments;
+	}
+}
+
+impl VisitMut for TypeResolver<'_> {
+	// Type arguments are resolved where they're written, before they're
+	// substituted into an alias's target, which was already resolved in the
+	// alias's module; going over the expansion again would expand recursive
+	// aliases forever
+	fn visit_type_mut(&mut self, ty: &mut Type) {
+		visit_mut::visit_type_mut(self, ty);
+		if let Some(expanded) = self.expand(ty) {
+			*ty = expanded;
+		} else if let Type::Path(type_path) = ty {
+			if type_path.qself.is_none() {
+				self.canonicalize(&mut type_path.path);
+			}
+		}
+	}
+
+	fn visit_trait_bound_mut(&mut self, bound: &mut TraitBound) {
+		self.canonicalize(&mut bound.path);
+		visit_mut::visit_trait_bound_mut(self, bound);
+	}
+
+	fn visit_item_impl_mut(&mut self, item_impl: &mut ItemImpl) {
+		if let Some((_, path, _)) = &mut item_impl.trait_ {
+			self.canonicalize(path);
+		}
+		visit_mut::visit_item_impl_mut(self, item_impl);
+	}
+
+	// Nested modules have aliases of their own, and are expanded when their
+	// items are collected
+	fn visit_item_mod_mut(&mut self, _module: &mut ItemMod) {}
+}
+
+// The names of a path's segments, without their generic arguments
+fn path_names(path: &syn::Path) -> Vec<String> {
+	path.segments
+		.iter()
+		.map(|segment| segment.ident.to_string())
+		.collect()
+}
+
+// Add a function or method, once per instantiation if it's generic
+fn collect_function(
+	attrs: &[Attribute],
+	sig: &Signature,
+	module_path: &[String],
+	self_type: Option<&str>,
+	api: &mut Api,
+) {
+	let item = match self_type {
+		Some(self_type) => format!("method `{}::{}`", self_type, sig.ident),
+		None => format!("function `{}`", sig.ident),
+	};
+	let params = match type_params(&sig.generics) {
+		Ok(params) => params,
+		Err(reason) => {
+			api.diagnostics
+				.push(format!("skipping {}: {}", item, reason));
+			return;
+		}
+	};
+
+	let mut signatures = Vec::new();
+	if params.is_empty() {
+		signatures.push((None, sig.clone()));
+	}
+	for (instance, args) in instantiations(attrs, &sig.ident, &params, api) {
+		let generic_sig = Signature {
+			generics: Generics::default(),
+			..sig.clone()
+		};
+		match substitute(&generic_sig, &params, &args) {
+			Ok(sig) => signatures.push((Some(instance), sig)),
+			Err(reason) => api.diagnostics.push(format!(
+				"skipping {} instantiated as `{}`: {}",
+				item,
+				instance.name(),
+				reason
+			)),
+		}
+	}
+	if signatures.is_empty() {
+		api.diagnostics.push(format!(
+			"skipping {}: it is generic and no instantiation was requested",
+			item
+		));
+	}
+
+	for (instance, sig) in signatures {
+		let func =
+			parse_signature(&sig, module_path, self_type).and_then(|func| {
+				Ok(Function {
+					options: parse_options(attrs)?,
+					..func
+				})
+			});
+		match func {
+			Ok(func) => api.functions.push(Function {
+				name: instance
+					.as_ref()
+					.map_or(func.name.clone(), Instance::name),
+				instance,
+				..func
+			}),
+			Err(reason) => api
+				.diagnostics
+				.push(format!("skipping {}: {}", item, reason)),
+		}
+	}
+}
+
+// Add a struct, or one struct per instantiation of a generic struct
+fn collect_struct(
+	struct_item: &ItemStruct,
+	module_path: &[String],
+	api: &mut Api,
+) {
+	let params = match type_params(&struct_item.generics) {
+		Ok(params) => params,
+		Err(reason) => {
+			api.diagnostics.push(format!(
+				"skipping struct `{}`: {}",
+				struct_item.ident, reason
+			));
+			return;
+		}
+	};
+	if params.is_empty() {
+		api.structs.push(parse_struct(struct_item, module_path));
+		collect_derives(
+			&struct_item.attrs,
+			&struct_item.ident.to_string(),
+			api,
+		);
+		return;
+	}
+
+	let instances =
+		instantiations(&struct_item.attrs, &struct_item.ident, &params, api);
+	if instances.is_empty() {
+		api.diagnostics.push(format!(
+			"skipping struct `{}`: it is generic and no instantiation was requested",
+			struct_item.ident
+		));
+	}
+	for (instance, args) in instances {
+		let generic_struct = ItemStruct {
+			generics: Generics::default(),
+			..struct_item.clone()
+		};
+		match substitute(&generic_struct, &params, &args) {
+			Ok(concrete) => {
+				let name = instance.name();
+				collect_derives(&struct_item.attrs, &name, api);
+				api.structs.push(Struct {
+					name,
+					instance: Some(instance),
+					..parse_struct(&concrete, module_path)
+				});
+			}
+			Err(reason) => api.diagnostics.push(format!(
+				"skipping struct `{}`: {}",
+				instance.name(),
+				reason
+			)),
+		}
+	}
+}
+
+// Add generic impl blocks once per instantiation of their self type they
+// apply to, e.g. `impl<T> Matrix<T>` for `Matrix<f32>`
+fn collect_generic_impls(generic_impls: GenericImpls, api: &mut Api) {
+	for (item_impl, module_path) in generic_impls {
+		let Type::Path(self_ty) = &*item_impl.self_ty else {
+			continue;
+		};
+		let segment = self_ty.path.segments.last().unwrap();
+		let self_type = quote! { #self_ty }.to_string();
+		let params = match type_params(&item_impl.generics) {
+			Ok(params) => params,
+			Err(reason) => {
+				api.diagnostics.push(format!(
+					"skipping impl block for `{}`: {}",
+					self_type, reason
+				));
+				continue;
+			}
+		};
+
+		let instances = api
+			.structs
+			.iter()
+			.filter_map(|s| s.instance.clone())
+			.filter(|instance| segment.ident == instance.generic_name)
+			.collect::<Vec<_>>();
+		let mut instantiated = false;
+		for instance in instances {
+			let Some(args) = bind_type_params(&params, segment, &instance)
+			else {
+				continue;
+			};
+			instantiated = true;
+			match instantiate_impl(&item_impl, &params, &args) {
+				Ok(concrete) if concrete.trait_.is_none() => {
+					collect_inherent_impl(&concrete, &module_path, api)
+				}
+				Ok(concrete) => collect_trait_impl(&concrete, api),
+				Err(reason) => api.diagnostics.push(format!(
+					"skipping impl block for `{}`: {}",
+					instance.name(),
+					reason
+				)),
+			}
+		}
+		// Blanket trait impls and impls for other crates' types are expected
+		if !instantiated && item_impl.trait_.is_none() {
+			api.diagnostics.push(format!(
+				"skipping impl block for `{}`: no instantiation of `{}` matches it",
+				self_type, segment.ident
+			));
+		}
+	}
+}
+
+// Arguments for an impl block's type parameters that turn its self type into
+// `instance`, or `None` when the impl doesn't apply to it
+fn bind_type_params(
+	params: &[Ident],
+	segment: &syn::PathSegment,
+	instance: &Instance,
+) -> Option<Vec<Type>> {
+	let PathArguments::AngleBracketed(self_args) = &segment.arguments else {
+		return None;
+	};
+	let self_args = self_args
+		.args
+		.iter()
+		.filter_map(|arg| match arg {
+			GenericArgument::Type(ty) => Some(ty),
+			_ => None,
+		})
+		.collect::<Vec<_>>();
+	if self_args.len() != instance.args.len() {
+		return None;
+	}
+
+	let mut bound: Vec<Option<Type>> = vec![None; params.len()];
+	for (self_arg, arg) in self_args.into_iter().zip(&instance.args) {
+		let arg = syn::parse_str::<Type>(arg).ok()?;
+		let param = match self_arg {
+			Type::Path(path) if path.qself.is_none() => path
+				.path
+				.get_ident()
+				.and_then(|ident| params.iter().position(|p| p == ident)),
+			_ => None,
+		};
+		// A parameter used twice has to stand for the same type both times
+		let expected = match param {
+			Some(index) => bound[index].get_or_insert(arg.clone()),
+			None => self_arg,
+		};
+		if quote! { #expected }.to_string() != quote! { #arg }.to_string() {
+			return None;
+		}
+	}
+	bound.into_iter().collect()
+}
+
+// Copy of a generic impl block with its type parameters replaced in the self
+// type and signatures; bodies are left alone since only signatures are read
+fn instantiate_impl(
+	item_impl: &ItemImpl,
+	params: &[Ident],
+	args: &[Type],
+) -> Result<ItemImpl, String> {
+	let mut concrete = ItemImpl {
+		generics: Generics::default(),
+		self_ty: Box::new(substitute(&*item_impl.self_ty, params, args)?),
+		..item_impl.clone()
+	};
+	for impl_item in &mut concrete.items {
+		match impl_item {
+			ImplItem::Fn(method) => {
+				method.sig = substitute(&method.sig, params, args)?
+			}
+			ImplItem::Type(assoc) => {
+				assoc.ty = substitute(&assoc.ty, params, args)?
+			}
+			_ => {}
+		}
+	}
+	Ok(concrete)
+}
+
+// Type parameters of a generic item; lifetimes are left to the compiler
+fn type_params(generics: &Generics) -> Result<Vec<Ident>, String> {
+	let mut params = Vec::new();
+	for param in &generics.params {
+		match param {
+			GenericParam::Type(param) => params.push(param.ident.clone()),
+			GenericParam::Const(param) => {
+				return Err(format!(
+					"const generic parameter `{}` is not supported",
+					param.ident
+				))
+			}
+			GenericParam::Lifetime(_) => {}
+		}
+	}
+	Ok(params)
+}
+
+// Instantiations of a generic item, from its `/// cbt: instantiate = f32, u8`
+// annotations (one per instantiation) and from the configured list, each
+// with its parsed type arguments
+fn instantiations(
+	attrs: &[Attribute],
+	name: &Ident,
+	params: &[Ident],
+	api: &mut Api,
+) -> Vec<(Instance, Vec<Type>)> {
+	let annotated = annotations(attrs)
+		.into_iter()
+		.filter(|(key, _)| key == "instantiate")
+		.map(|(_, value)| {
+			let value =
+				value.ok_or("`cbt: instantiate` needs a list of types")?;
+			Punctuated::<Type, Token![,]>::parse_terminated
+				.parse_str(&value)
+				.map(|args| args.into_iter().collect::<Vec<_>>())
+				.map_err(|error| {
+					format!("invalid type in `{}`: {}", value, error)
+				})
+		});
+	let configured = api
+		.instantiations
+		.iter()
+		.filter(|instance| *name == instance.generic_name)
+		.map(|instance| {
+			instance
+				.args
+				.iter()
+				.map(|arg| syn::parse_str::<Type>(arg))
+				.collect::<Result<Vec<_>, _>>()
+				.map_err(|error| error.to_string())
+		});
+	let requested = annotated.chain(configured).collect::<Vec<_>>();
+
+	let mut instances: Vec<(Instance, Vec<Type>)> = Vec::new();
+	for args in requested {
+		let args = args.and_then(|args| {
+			if args.len() == params.len() {
+				Ok(args)
+			} else {
+				Err(format!(
+					"expected {} type arguments, got {}",
+					params.len(),
+					args.len()
+				))
+			}
+		});
+		let args = match args {
+			Ok(args) => args,
+			Err(reason) => {
+				api.diagnostics.push(format!(
+					"ignoring instantiation of `{}`: {}",
+					name, reason
+				));
+				continue;
+			}
+		};
+		let instance = Instance {
+			generic_name: name.to_string(),
+			args: args.iter().map(|arg| quote! { #arg }.to_string()).collect(),
+		};
+		if instances.iter().all(|(existing, _)| *existing != instance) {
+			instances.push((instance, args));
+		}
+	}
+	instances
+}
+
+// Replace type parameters with concrete types in a signature, struct or type
+fn substitute<T: Parse + ToTokens>(
+	node: &T,
+	params: &[Ident],
+	args: &[Type],
+) -> Result<T, String> {
+	syn::parse2(substitute_tokens(node.to_token_stream(), params, args))
+		.map_err(|error| error.to_string())
+}
+
+fn substitute_tokens(
+	tokens: TokenStream,
+	params: &[Ident],
+	args: &[Type],
+) -> TokenStream {
+	tokens
+		.into_iter()
+		.flat_map(|tree| match tree {
+			TokenTree::Ident(ident) => {
+				match params.iter().position(|param| *param == ident) {
+					Some(index) => args[index].to_token_stream(),
+					None => TokenTree::Ident(ident).into(),
+				}
+			}
+			TokenTree::Group(group) => {
+				let mut substituted = Group::new(
+					group.delimiter(),
+					substitute_tokens(group.stream(), params, args),
+				);
+				substituted.set_span(group.span());
+				TokenTree::Group(substituted).into()
+			}
+			tree => tree.into(),
+		})
+		.collect()
+}
+
+// Read the `/// cbt: key = value` annotations of a function
+fn parse_options(attrs: &[Attribute]) -> Result<FunctionOptions, String> {
+	let mut options = FunctionOptions::default();
+	for (key, value) in annotations(attrs) {
+		match (key.as_str(), value) {
+			("panic_value", Some(value)) => {
+				syn::parse_str::<Expr>(&value).map_err(|error| {
+					format!("invalid `panic_value` expression: {}", error)
+				})?;
+				options.panic_value = Some(value);
+			}
+			("assume_nonnull", None) => options.assume_nonnull = true,
+			("utf8", Some(value)) => options.utf8 = Some(value.parse()?),
+			// Read by `instantiations` before the signature is parsed
+			("instantiate", _) => {}
+			(key, _) => {
+				return Err(format!("unknown annotation `cbt: {}`", key));
+			}
+		}
+	}
+	Ok(options)
+}
+
+// Doc comment lines of the form `cbt: key` or `cbt: key = value`
+fn annotations(attrs: &[Attribute]) -> Vec<(String, Option<String>)> {
+	attrs
+		.iter()
+		.filter(|attr| attr.path().is_ident("doc"))
+		.filter_map(|attr| match &attr.meta.require_name_value().ok()?.value {
+			Expr::Lit(ExprLit {
+				lit: Lit::Str(doc), ..
+			}) => Some(doc.value()),
+			_ => None,
+		})
+		.filter_map(|doc| {
+			let annotation =
+				doc.trim().strip_prefix("cbt:")?.trim().to_string();
+			Some(match annotation.split_once('=') {
+				Some((key, value)) => {
+					(key.trim().to_string(), Some(value.trim().to_string()))
+				}
+				None => (annotation, None),
+			})
+		})
+		.collect()
+}
+
+// Add the public methods and associated functions of an inherent impl block
+fn collect_inherent_impl(
+	item_impl: &ItemImpl,
+	module_path: &[String],
+	api: &mut Api,
+) {
+	let Some(self_type) = impl_self_type(item_impl) else {
+		return;
+	};
+	if !item_impl.generics.params.is_empty() {
+		api.diagnostics.push(format!(
+			"skipping impl block for `{}`: impl blocks with lifetime parameters are not supported",
+			self_type
+		));
+		return;
+	}
+
+	for impl_item in &item_impl.items {
+		let ImplItem::Fn(method) = impl_item else {
+			continue;
+		};
+		if !matches!(method.vis, Visibility::Public(_)) {
+			continue;
+		}
+		collect_function(
+			&method.attrs,
+			&method.sig,
+			module_path,
+			Some(&self_type),
+			api,
+		);
+	}
+}
+
+// Model name of an impl block's self type, e.g. `MatrixF32` for `Matrix<f32>`
+fn impl_self_type(item_impl: &ItemImpl) -> Option<String> {
+	match parse_type(&item_impl.self_ty) {
+		TypeRef::Named(name) => Some(name),
+		_ => None,
+	}
+}
+
+// Record which type an `impl Trait for Type` block is for
+fn collect_trait_impl(item_impl: &ItemImpl, api: &mut Api) {
+	let Some((_, trait_path, _)) = &item_impl.trait_ else {
+		return;
+	};
+	if let Some(self_type) = impl_self_type(item_impl) {
+		let trait_name = trait_name(trait_path);
+		// Iterators also need their item type
+		let item = (trait_name == "Iterator")
+			.then(|| {
+				item_impl.items.iter().find_map(|item| match item {
+					ImplItem::Type(assoc) if assoc.ident == "Item" => {
+						Some(parse_type(&assoc.ty))
+					}
+					_ => None,
+				})
+			})
+			.flatten();
+		api.trait_impls.push(TraitImpl {
+			trait_name,
+			self_type,
+			item,
+		});
+	}
+}
+
+// Parse an object-safe trait whose methods can be put in a vtable
+fn parse_trait(
+	item_trait: &ItemTrait,
+	module_path: &[String],
+) -> Result<Trait, String> {
+	let trait_name = item_trait.ident.to_string();
+	if !item_trait.generics.params.is_empty() {
+		return Err("generic traits are not supported".to_string());
+	}
+
+	let mut methods = Vec::new();
+	for trait_item in &item_trait.items {
+		let TraitItem::Fn(method) = trait_item else {
+			continue;
+		};
+		let method_name = &method.sig.ident;
+		if !method.sig.generics.params.is_empty() {
+			return Err(format!("method `{}` is generic", method_name));
+		}
+
+		let func = Function {
+			options: parse_options(&method.attrs)?,
+			..parse_signature(&method.sig, module_path, Some(&trait_name))?
+		};
+		let self_type = TypeRef::Named(trait_name.clone());
+		let takes_reference = matches!(
+			func.inputs.first(),
+			Some(Param { name, ty: TypeRef::Reference { .. }, .. }) if name == "self"
+		);
+		if !takes_reference {
+			return Err(format!(
+				"method `{}` does not take `&self` or `&mut self`",
+				method_name
+			));
+		}
+		if func.inputs[1..]
+			.iter()
+			.map(|param| &param.ty)
+			.chain(std::iter::once(&func.output))
+			.any(|ty| mentions_type(ty, &self_type))
+		{
+			return Err(format!("method `{}` uses `Self`", method_name));
+		}
+		methods.push(func);
+	}
+
+	Ok(Trait {
+		name: trait_name,
+		module_path: module_path.to_vec(),
+		methods,
+		c_implementable: false,
+	})
+}
+
+// Whether `needle` appears anywhere inside `ty`
+fn mentions_type(ty: &TypeRef, needle: &TypeRef) -> bool {
+	ty == needle
+		|| match ty {
+			TypeRef::Vec(inner)
+			| TypeRef::Option(inner)
+			| TypeRef::Boxed(inner)
+			| TypeRef::Reference { ty: inner, .. }
+			| TypeRef::Slice { ty: inner, .. }
+			| TypeRef::Iterator(inner) => mentions_type(inner, needle),
+			_ => false,
+		}
+}
+
+// Parse a function signature, resolving `Self` for methods and associated functions
+fn parse_signature(
+	sig: &Signature,
+	module_path: &[String],
+	self_type: Option<&str>,
+) -> Result<Function, String> {
+	let resolve = |ty: TypeRef| match self_type {
+		Some(self_type) => resolve_self(ty, self_type),
+		None => ty,
+	};
+
+	let mut inputs = Vec::new();
+	for input in &sig.inputs {
+		match input {
+			FnArg::Receiver(receiver) => {
+				let ty = resolve(parse_type(&receiver.ty));
+				let self_type = TypeRef::Named(self_type.unwrap().to_string());
+				let supported = match &ty {
+					TypeRef::Reference { ty, .. } => **ty == self_type,
+					ty => *ty == self_type,
+				};
+				if !supported {
+					return Err(format!(
+						"unsupported receiver type {}",
+						quote! { #receiver }
+					));
+				}
+				inputs.push(Param::new("self".to_string(), ty));
+			}
+			FnArg::Typed(PatType { pat, ty, .. }) => inputs.push(Param::new(
+				param_name(pat, inputs.len()),
+				resolve(parse_type(ty)),
+			)),
+		}
+	}
+
+	let output = resolve(parse_return_type(&sig.output));
+	Ok(Function {
+		name: sig.ident.to_string(),
+		module_path: module_path.to_vec(),
+		self_type: self_type.map(str::to_string),
+		inputs,
+		output_ownership: output.ownership(),
+		output,
+		options: FunctionOptions::default(),
+		instance: None,
+	})
+}
+
+// Name the wrappers give a parameter: its binding without `mut` or `r#`, or
+// `arg<index>` for `_` and destructuring patterns
+fn param_name(pat: &Pat, index: usize) -> String {
+	match pat {
+		Pat::Ident(PatIdent { ident, .. }) => ident.unraw().to_string(),
+		_ => format!("arg{}", index),
+	}
+}
+
+// Replace `Self` with the implementing type
+fn resolve_self(ty: TypeRef, self_type: &str) -> TypeRef {
+	match ty {
+		TypeRef::Named(name) if name == "Self" => {
+			TypeRef::Named(self_type.to_string())
+		}
+		TypeRef::Vec(inner) => {
+			TypeRef::Vec(Box::new(resolve_self(*inner, self_type)))
+		}
+		TypeRef::Option(inner) => {
+			TypeRef::Option(Box::new(resolve_self(*inner, self_type)))
+		}
+		TypeRef::Boxed(inner) => {
+			TypeRef::Boxed(Box::new(resolve_self(*inner, self_type)))
+		}
+		TypeRef::Reference { mutable, ty } => TypeRef::Reference {
+			mutable,
+			ty: Box::new(resolve_self(*ty, self_type)),
+		},
+		TypeRef::Slice { mutable, ty } => TypeRef::Slice {
+			mutable,
+			ty: Box::new(resolve_self(*ty, self_type)),
+		},
+		TypeRef::Iterator(item) => {
+			TypeRef::Iterator(Box::new(resolve_self(*item, self_type)))
+		}
+		ty => ty,
+	}
+}
+
+fn parse_return_type(output: &ReturnType) -> TypeRef {
+	match output {
+		ReturnType::Default => TypeRef::Unit,
+		ReturnType::Type(_, ty) => parse_type(ty),
+	}
+}
+
+fn parse_struct(struct_item: &ItemStruct, module_path: &[String]) -> Struct {
+	Struct {
+		name: struct_item.ident.to_string(),
+		module_path: module_path.to_vec(),
+		fields: parse_fields(&struct_item.fields),
+		repr_c: has_repr_c(&struct_item.attrs),
+		by_value: false,
+		constructor: Constructor::None,
+		instance: None,
+	}
+}
+
+// Record `#[derive(Trait)]`s like `impl Trait for Type` blocks
+fn collect_derives(attrs: &[Attribute], self_type: &str, api: &mut Api) {
+	let derives = attrs
+		.iter()
+		.filter(|attr| attr.path().is_ident("derive"))
+		.filter_map(|attr| {
+			attr.parse_args_with(
+				Punctuated::<syn::Path, Token![,]>::parse_terminated,
+			)
+			.ok()
+		})
+		.flatten();
+	for path in derives {
+		if let Some(trait_segment) = path.segments.last() {
+			api.trait_impls.push(TraitImpl {
+				trait_name: trait_segment.ident.to_string(),
+				self_type: self_type.to_string(),
+				item: None,
+			});
+		}
+	}
+}
+
+// Whether a `#[repr(...)]` attribute lists `C`, e.g. `#[repr(C, align(8))]`
+fn has_repr_c(attrs: &[Attribute]) -> bool {
+	attrs
+		.iter()
+		.filter(|attr| attr.path().is_ident("repr"))
+		.filter_map(|attr| {
+			attr.parse_args_with(
+				Punctuated::<Meta, Token![,]>::parse_terminated,
+			)
+			.ok()
+		})
+		.flatten()
+		.any(|meta| meta.path().is_ident("C"))
+}
+
+fn parse_enum(
+	enum_item: &ItemEnum,
+	module_path: &[String],
+) -> Result<Enum, String> {
+	if !type_params(&enum_item.generics)?.is_empty() {
+		return Err("generic enums are not supported".to_string());
+	}
+
+	let mut variants = Vec::new();
+	let mut next_discriminant = 0i64;
+
+	for variant in &enum_item.variants {
+		let discriminant = match &variant.discriminant {
+			Some((_, expr)) => parse_discriminant(expr).ok_or_else(|| {
+				format!(
+					"discriminant of `{}` is not an integer literal",
+					variant.ident
+				)
+			})?,
+			None => next_discriminant,
+		};
+
+		// C enumerators must be representable as an `int`
+		if i32::try_from(discriminant).is_err() {
+			return Err(format!(
+				"discriminant of `{}` does not fit in a C int",
+				variant.ident
+			));
+		}
+
+		variants.push(Variant {
+			name: variant.ident.to_string(),
+			discriminant,
+			fields: parse_fields(&variant.fields),
+		});
+		next_discriminant = discriminant + 1;
+	}
+
+	Ok(Enum {
+		name: enum_item.ident.to_string(),
+		module_path: module_path.to_vec(),
+		variants,
+	})
+}
+
+// Evaluate an explicit discriminant, which must be a (possibly negated) integer literal
+fn parse_discriminant(expr: &Expr) -> Option<i64> {
+	match expr {
+		Expr::Lit(ExprLit {
+			lit: Lit::Int(int), ..
+		}) => int.base10_parse().ok(),
+		Expr::Unary(ExprUnary {
+			op: UnOp::Neg(_),
+			expr,
+			..
+		}) => parse_discriminant(expr)?.checked_neg(),
+		Expr::Paren(paren) => parse_discriminant(&paren.expr),
+		Expr::Group(group) => parse_discriminant(&group.expr),
+		_ => None,
+	}
+}
+
+fn parse_fields(fields: &Fields) -> Vec<Field> {
+	fields
+		.iter()
+		.map(|field| Field {
+			name: field.ident.as_ref().map(|ident| ident.to_string()),
+			public: matches!(field.vis, Visibility::Public(_)),
+			ty: parse_type(&field.ty),
+		})
+		.collect()
+}
+
+// Map a syn type to the model's type reference
+pub fn parse_type(ty: &Type) -> TypeRef {
+	match ty {
+		// `<T as Trait>::Assoc` is left for the compiler to work out
+		Type::Path(type_path) if type_path.qself.is_some() => {
+			TypeRef::Unsupported(quote! { #ty }.to_string())
+		}
+		// Paths to the crate's own types were already cut down to the type's
+		// name, so any other path leads outside the crate
+		Type::Path(type_path)
+			if type_path.path.segments.len() > 1
+				&& !is_std_type(&type_path.path) =>
+		{
+			TypeRef::Unsupported(quote! { #ty }.to_string())
+		}
+		Type::Path(type_path) => {
+			let segment = type_path.path.segments.last().unwrap();
+			let ident = segment.ident.to_string();
+			if let Some(primitive) = Primitive::from_ident(&ident) {
+				return TypeRef::Primitive(primitive);
+			}
+
+			match ident.as_str() {
+				"char" => TypeRef::Char,
+				// C has no standard 128-bit integer type
+				"i128" | "u128" => TypeRef::Unsupported(ident),
+				"String" => TypeRef::String,
+				"Vec" => TypeRef::Vec(Box::new(parse_generic_arg(
+					&segment.arguments,
+					ty,
+				))),
+				"Option" => TypeRef::Option(Box::new(parse_generic_arg(
+					&segment.arguments,
+					ty,
+				))),
+				// `Box<dyn Iterator>` is boxed again for C like `impl Iterator`
+				"Box" => match parse_generic_arg(&segment.arguments, ty) {
+					TypeRef::Iterator(item) => TypeRef::Iterator(item),
+					inner => TypeRef::Boxed(Box::new(inner)),
+				},
+				"Result" => parse_result(&segment.arguments, ty),
+				// Generic structs are known by their instantiations' names
+				_ => match generic_type_args(&segment.arguments) {
+					Some(args) => TypeRef::Named(
+						Instance {
+							generic_name: ident,
+							args,
+						}
+						.name(),
+					),
+					None => TypeRef::Named(ident),
+				},
+			}
+		}
+		Type::Reference(reference) => match &*reference.elem {
+			Type::Path(p) if p.path.is_ident("str") => {
+				match &reference.lifetime {
+					Some(lifetime) if lifetime.ident == "static" => {
+						TypeRef::StaticStr
+					}
+					_ => TypeRef::Str,
+				}
+			}
+			Type::Slice(slice) => TypeRef::Slice {
+				mutable: reference.mutability.is_some(),
+				ty: Box::new(parse_type(&slice.elem)),
+			},
+			elem => TypeRef::Reference {
+				mutable: reference.mutability.is_some(),
+				ty: Box::new(parse_type(elem)),
+			},
+		},
+		Type::TraitObject(trait_object) => {
+			parse_iterator(&trait_object.bounds, ty)
+				.or_else(|| {
+					main_trait_bound(&trait_object.bounds)
+						.map(TypeRef::DynTrait)
+				})
+				.unwrap_or_else(|| {
+					TypeRef::Unsupported(quote! { #ty }.to_string())
+				})
+		}
+		Type::ImplTrait(impl_trait) => parse_iterator(&impl_trait.bounds, ty)
+			.or_else(|| {
+				main_trait_bound(&impl_trait.bounds).map(TypeRef::ImplTrait)
+			})
+			.unwrap_or_else(|| {
+				TypeRef::Unsupported(quote! { #ty }.to_string())
+			}),
+		Type::Tuple(tuple) if tuple.elems.is_empty() => TypeRef::Unit,
+		Type::Paren(paren) => parse_type(&paren.elem),
+		Type::Group(group) => parse_type(&group.elem),
+		_ => TypeRef::Unsupported(quote! { #ty }.to_string()),
+	}
+}
+
+// Standard library types cbt maps, by their path inside `std`, `core` or
+// `alloc`
+const STD_TYPE_PATHS: &[&[&str]] = &[
+	&["string", "String"],
+	&["vec", "Vec"],
+	&["boxed", "Box"],
+	&["option", "Option"],
+	&["result", "Result"],
+	&["io", "Result"],
+];
+
+// Whether a path like `std::string::String` names a type `parse_type` knows
+// by its last segment
+fn is_std_type(path: &syn::Path) -> bool {
+	let names = path_names(path);
+	let Some((root, rest)) = names.split_first() else {
+		return false;
+	};
+	let primitive = matches!(rest, [module, name]
+		if module == "primitive"
+			&& (Primitive::from_ident(name).is_some() || name == "char"));
+	matches!(root.as_str(), "std" | "core" | "alloc")
+		&& (primitive
+			|| STD_TYPE_PATHS.iter().any(|std_path| *std_path == rest))
+}
+
+// Name a trait is known by: its own name for the crate's traits and the
+// standard library's, e.g. `Display` for `std::fmt::Display`, and its full
+// path for any other crate's, so they never pass for one of the crate's
+fn trait_name(path: &syn::Path) -> String {
+	let names = path_names(path);
+	match names.first().map(String::as_str) {
+		Some("std" | "core" | "alloc") | None => {
+			names.last().cloned().unwrap_or_default()
+		}
+		_ if names.len() == 1 => names[0].clone(),
+		_ => names.join("::"),
+	}
+}
+
+// `impl Iterator<Item = T>` or `dyn Iterator<Item = T>`; iterators borrowing
+// something can't outlive the call, so they stay unsupported
+fn parse_iterator(
+	bounds: &Punctuated<TypeParamBound, Token![+]>,
+	ty: &Type,
+) -> Option<TypeRef> {
+	let segment = bounds.iter().find_map(|bound| match bound {
+		TypeParamBound::Trait(bound)
+			if trait_name(&bound.path) == "Iterator" =>
+		{
+			bound.path.segments.last()
+		}
+		_ => None,
+	})?;
+	let borrows = bounds.iter().any(|bound| {
+		matches!(bound, TypeParamBound::Lifetime(lifetime) if lifetime.ident != "static")
+	});
+	let item = match &segment.arguments {
+		PathArguments::AngleBracketed(args) if !borrows => {
+			args.args.iter().find_map(|arg| match arg {
+				GenericArgument::AssocType(assoc) if assoc.ident == "Item" => {
+					Some(parse_type(&assoc.ty))
+				}
+				_ => None,
+			})
+		}
+		_ => None,
+	};
+	Some(match item {
+		Some(item) => TypeRef::Iterator(Box::new(item)),
+		None => TypeRef::Unsupported(quote! { #ty }.to_string()),
+	})
+}
+
+// Name of the trait in `dyn Trait + Send` or `impl Trait + 'a`, skipping
+// auto traits and lifetimes
+fn main_trait_bound(
+	bounds: &Punctuated<TypeParamBound, Token![+]>,
+) -> Option<String> {
+	bounds.iter().find_map(|bound| match bound {
+		TypeParamBound::Trait(bound) => {
+			let name = trait_name(&bound.path);
+			let auto_trait = matches!(name.as_str(), "Send" | "Sync" | "Unpin");
+			(!auto_trait).then_some(name)
+		}
+		_ => None,
+	})
+}
+
+// Parse `Result<T, E>`; aliases such as `io::Result<T>` leave the error type
+// unknown, which is fine since errors are only ever formatted
+fn parse_result(arguments: &PathArguments, ty: &Type) -> TypeRef {
+	let mut types = Vec::new();
+	if let PathArguments::AngleBracketed(args) = arguments {
+		for arg in &args.args {
+			if let GenericArgument::Type(inner) = arg {
+				types.push(parse_type(inner));
+			}
+		}
+	}
+
+	let mut types = types.into_iter();
+	match types.next() {
+		Some(ok) => TypeRef::Result {
+			ok: Box::new(ok),
+			err: Box::new(types.next().unwrap_or_else(|| {
+				TypeRef::Unsupported(quote! { #ty }.to_string())
+			})),
+		},
+		None => TypeRef::Unsupported(quote! { #ty }.to_string()),
+	}
+}
+
+// Rust source of the type arguments in `Matrix<f32>`, if there are any
+fn generic_type_args(arguments: &PathArguments) -> Option<Vec<String>> {
+	let PathArguments::AngleBracketed(args) = arguments else {
+		return None;
+	};
+	let args = args
+		.args
+		.iter()
+		.filter_map(|arg| match arg {
+			GenericArgument::Type(ty) => Some(quote! { #ty }.to_string()),
+			_ => None,
+		})
+		.collect::<Vec<_>>();
+	(!args.is_empty()).then_some(args)
+}
+
+// Parse the first generic type argument, e.g. the `T` in `Vec<T>`
+fn parse_generic_arg(arguments: &PathArguments, ty: &Type) -> TypeRef {
+	if let PathArguments::AngleBracketed(args) = arguments {
+		for arg in &args.args {
+			if let GenericArgument::Type(inner) = arg {
+				return parse_type(inner);
+			}
+		}
+	}
+	TypeRef::Unsupported(quote! { #ty }.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::model::Primitive::{U16, U32, U64, U8};
+
+	fn collect(source: &str, instantiations: &[&str]) -> Api {
+		let file = syn::parse_file(source).unwrap();
+		let instantiations = instantiations
+			.iter()
+			.map(|instance| instance.to_string())
+			.collect::<Vec<_>>();
+		collect_api(
+			&file.items,
+			Path::new("."),
+			false,
+			"test_crate",
+			"test_crate",
+			&instantiations,
+		)
+	}
+
+	fn function<'a>(api: &'a Api, name: &str) -> &'a Function {
+		api.functions
+			.iter()
+			.find(|func| func.c_name(api) == name)
+			.unwrap_or_else(|| panic!("no function `{}`", name))
+	}
+
+	// Type of the first parameter of the function exported as `name`
+	fn param_type<'a>(api: &'a Api, name: &str) -> &'a TypeRef {
+		&function(api, name).inputs[0].ty
+	}
+
+	fn named(name: &str) -> TypeRef {
+		TypeRef::Named(name.to_string())
+	}
+
+	fn reference(ty: TypeRef) -> TypeRef {
+		TypeRef::Reference {
+			mutable: false,
+			ty: Box::new(ty),
+		}
+	}
+
+	const MODELS: &str = "
+		pub mod models {
+			pub struct User {
+				pub id: u64,
+			}
+		}
+	";
+
+	#[test]
+	fn crate_self_and_super_paths_name_the_same_type() {
+		let api = collect(
+			&format!(
+				"{MODELS}
+				pub fn from_crate(user: crate::models::User) {{}}
+				pub fn from_self(user: self::models::User) {{}}
+				pub fn relative(user: models::User) {{}}
+				pub mod nested {{
+					pub fn from_super(user: super::models::User) {{}}
+					pub mod deeper {{
+						pub fn from_super_super(user: super::super::models::User) {{}}
+					}}
+				}}"
+			),
+			&[],
+		);
+		for name in [
+			"from_crate",
+			"from_self",
+			"relative",
+			"from_super",
+			"from_super_super",
+		] {
+			assert_eq!(*param_type(&api, name), named("User"), "{}", name);
+		}
+	}
+
+	#[test]
+	fn standard_library_paths_map_like_their_short_names() {
+		let api = collect(
+			"
+			pub fn string(text: std::string::String) {}
+			pub fn bytes(bytes: alloc::vec::Vec<u8>) {}
+			pub fn number(number: core::primitive::u32) {}
+			pub fn maybe(value: ::std::option::Option<u16>) {}
+			pub fn io(fail: bool) -> std::io::Result<u8> { Ok(0) }
+			",
+			&[],
+		);
+		assert_eq!(*param_type(&api, "string"), TypeRef::String);
+		assert_eq!(
+			*param_type(&api, "bytes"),
+			TypeRef::Vec(Box::new(TypeRef::Primitive(U8)))
+		);
+		assert_eq!(*param_type(&api, "number"), TypeRef::Primitive(U32));
+		assert_eq!(
+			*param_type(&api, "maybe"),
+			TypeRef::Option(Box::new(TypeRef::Primitive(U16)))
+		);
+		assert!(matches!(
+			&function(&api, "io").output,
+			TypeRef::Result { ok, .. } if **ok == TypeRef::Primitive(U8)
+		));
+	}
+
+	#[test]
+	fn renamed_imports_resolve_to_the_original_type() {
+		let api = collect(
+			&format!(
+				"{MODELS}
+				pub mod api {{
+					use crate::models::User as Member;
+					use super::models::{{self, User as Account}};
+					use std::string::String as Text;
+
+					pub fn member(member: Member) {{}}
+					pub fn account(account: &Account) {{}}
+					pub fn through_self(user: models::User) {{}}
+					pub fn text(text: Text) {{}}
+				}}"
+			),
+			&[],
+		);
+		assert_eq!(*param_type(&api, "member"), named("User"));
+		assert_eq!(*param_type(&api, "account"), reference(named("User")));
+		assert_eq!(*param_type(&api, "through_self"), named("User"));
+		assert_eq!(*param_type(&api, "text"), TypeRef::String);
+	}
+
+	#[test]
+	fn glob_imports_bring_in_types_and_aliases() {
+		let api = collect(
+			&format!(
+				"{MODELS}
+				pub mod ids {{
+					pub type UserId = u64;
+				}}
+				pub mod api {{
+					use crate::ids::*;
+					use crate::models::*;
+
+					pub fn user(user: User) {{}}
+					pub fn id(id: UserId) {{}}
+					pub fn qualified(id: self::UserId) {{}}
+				}}"
+			),
+			&[],
+		);
+		assert_eq!(*param_type(&api, "user"), named("User"));
+		assert_eq!(*param_type(&api, "id"), TypeRef::Primitive(U64));
+		assert_eq!(*param_type(&api, "qualified"), TypeRef::Primitive(U64));
+	}
+
+	#[test]
+	fn alias_chains_expand_through_every_alias() {
+		let api = collect(
+			"
+			pub type Outer = Middle;
+			type Middle = ids::Inner;
+			pub mod ids {
+				pub type Inner = u16;
+			}
+			type Res<T> = Result<T, String>;
+			mod hidden {
+				pub type Ticket = u32;
+			}
+
+			pub fn chained(value: Outer) {}
+			pub fn generic(fail: bool) -> Res<Outer> { Ok(0) }
+			pub fn private_module(ticket: crate::hidden::Ticket) {}
+			",
+			&[],
+		);
+		assert_eq!(*param_type(&api, "chained"), TypeRef::Primitive(U16));
+		assert_eq!(
+			function(&api, "generic").output,
+			TypeRef::Result {
+				ok: Box::new(TypeRef::Primitive(U16)),
+				err: Box::new(TypeRef::String),
+			}
+		);
+		assert_eq!(
+			*param_type(&api, "private_module"),
+			TypeRef::Primitive(U32)
+		);
+	}
+
+	#[test]
+	fn alias_cycles_are_left_unexpanded() {
+		let api = collect(
+			"
+			pub type Ping = Pong;
+			pub type Pong = Ping;
+			pub type Loop<T> = Option<Loop<T>>;
+
+			pub fn ping(value: Ping) {}
+			pub fn spin(value: Loop<u8>) {}
+			",
+			&[],
+		);
+		assert!(matches!(
+			param_type(&api, "ping"),
+			TypeRef::Named(name) if name == "Ping" || name == "Pong"
+		));
+		let mut ty = param_type(&api, "spin");
+		while let TypeRef::Option(inner) = ty {
+			ty = inner;
+		}
+		assert_eq!(*ty, named("LoopU8"));
+	}
+
+	#[test]
+	fn foreign_types_never_pass_for_the_crates_own() {
+		let api = collect(
+			"
+			pub mod errors {
+				pub struct Error {
+					pub code: i32,
+				}
+				pub type Handle = u32;
+			}
+			pub mod io_stuff {
+				use std::collections::HashMap;
+				use std::io::Error;
+				use std::os::fd::RawFd as Handle;
+
+				pub fn kind(error: &Error) {}
+				pub fn fd(handle: Handle) {}
+				pub fn counts(map: HashMap<String, i32>) {}
+				pub fn qualified(error: std::fmt::Error) {}
+				pub fn own(error: &crate::errors::Error) {}
+			}
+			",
+			&[],
+		);
+		assert_eq!(
+			*param_type(&api, "kind"),
+			reference(TypeRef::Unsupported("std :: io :: Error".to_string()))
+		);
+		assert_eq!(
+			*param_type(&api, "fd"),
+			TypeRef::Unsupported("std :: os :: fd :: RawFd".to_string())
+		);
+		assert!(matches!(
+			param_type(&api, "counts"),
+			TypeRef::Unsupported(source) if source.starts_with("std :: collections :: HashMap")
+		));
+		assert_eq!(
+			*param_type(&api, "qualified"),
+			TypeRef::Unsupported("std :: fmt :: Error".to_string())
+		);
+		assert_eq!(*param_type(&api, "own"), reference(named("Error")));
+	}
+
+	#[test]
+	fn only_the_first_type_with_a_name_is_exported() {
+		let api = collect(
+			"
+			pub mod cfg_a {
+				pub struct Config {
+					pub level: u8,
+				}
+				pub fn level(config: &Config) {}
+			}
+			pub mod cfg_b {
+				pub struct Config {
+					pub verbose: bool,
+				}
+				pub fn verbose(config: &Config) {}
+				pub fn other(config: &super::cfg_a::Config) {}
+			}
+			",
+			&[],
+		);
+		assert_eq!(*param_type(&api, "level"), reference(named("Config")));
+		assert_eq!(*param_type(&api, "other"), reference(named("Config")));
+		assert_eq!(
+			*param_type(&api, "verbose"),
+			reference(TypeRef::Unsupported(
+				"test_crate :: cfg_b :: Config".to_string()
+			))
+		);
+		assert_eq!(api.structs.len(), 1);
+		assert_eq!(api.structs[0].module_path, ["test_crate", "cfg_a"]);
+	}
+
+	#[test]
+	fn impls_bind_repeated_parameters_only_to_matching_arguments() {
+		let api = collect(
+			"
+			pub struct Duo<A, B> {
+				pub first: A,
+				pub second: B,
+			}
+			impl<T> Duo<T, T> {
+				pub fn same(&self) -> T { self.first }
+			}
+			impl<A, B> Duo<A, B> {
+				pub fn second(&self) -> &B { &self.second }
+			}
+			impl<B> Duo<u8, B> {
+				pub fn byte(&self) -> B { self.second }
+			}
+			",
+			&["Duo<u8, u8>", "Duo<u16, u32>"],
+		);
+		assert_eq!(
+			function(&api, "duo_u8_u8_same").output,
+			TypeRef::Primitive(U8)
+		);
+		assert!(!api
+			.functions
+			.iter()
+			.any(|func| func.c_name(&api) == "duo_u16_u32_same"));
+		assert_eq!(
+			function(&api, "duo_u16_u32_second").output,
+			reference(TypeRef::Primitive(U32))
+		);
+		assert_eq!(
+			function(&api, "duo_u8_u8_byte").output,
+			TypeRef::Primitive(U8)
+		);
+		assert!(!api
+			.functions
+			.iter()
+			.any(|func| func.c_name(&api) == "duo_u16_u32_byte"));
+	}
+}
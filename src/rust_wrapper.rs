@@ -0,0 +1,2200 @@
+// Emit the Rust `extern "C"` wrapper crate for an API model
+
+use crate::c_header::{
+	iterator_type, out_param_name, slice_len_name, string_free_name,
+	takes_length, union_member_name, vec_type,
+};
+use crate::model::{
+	Api, Constructor, Enum, Field, Function, Param, Struct, Trait, TypeRef,
+	Utf8Policy, STATUS_CODES,
+};
+use crate::naming::{c_identifier, identifier};
+use convert_case::{Case, Casing};
+
+// Generate the wrapper crate's `lib.rs`
+pub fn generate(api: &Api) -> String {
+	let mut rust_exports = vec![
+		generate_rust_error_handling(api),
+		generate_rust_strings(api),
+	];
+
+	for enum_item in api.enums.iter().filter(|e| e.is_c_like()) {
+		rust_exports.push(generate_rust_enum_conversion(enum_item));
+	}
+
+	for enum_item in api.enums.iter().filter(|e| !e.is_c_like()) {
+		rust_exports.push(generate_rust_tagged_union(api, enum_item));
+	}
+
+	for enum_item in &api.enums {
+		rust_exports
+			.push(generate_rust_enum_formatting_functions(api, enum_item));
+	}
+
+	for element in api.vec_element_types() {
+		rust_exports.push(generate_rust_vec(api, element));
+	}
+
+	for value in api.option_value_types() {
+		rust_exports.push(format!(
+			"\n#[repr(C)]\n#[derive(Clone, Copy)]\npub struct {} {{\n    pub has_value: bool,\n    pub value: {},\n}}\n",
+			option_mirror_name(value),
+			rust_type_to_rust_extern_c(api, value),
+		));
+	}
+
+	for struct_item in &api.structs {
+		rust_exports.push(generate_rust_struct_wrapper(api, struct_item));
+	}
+
+	for item in api.iterator_item_types() {
+		rust_exports.push(generate_rust_iterator(api, item));
+	}
+
+	for (struct_item, item) in api.iterator_structs() {
+		rust_exports.push(format!(
+			r#"
+#[no_mangle]
+pub extern "C" fn {}_next(iter: *mut {}, out: *mut {}) -> bool {{
+{}}}
+"#,
+			api.c_type_name(&struct_item.name),
+			struct_item.rust_path(),
+			return_type_to_rust_extern_c(api, item),
+			iterator_next_body(
+				api,
+				item,
+				"Iterator::next(unsafe { &mut *iter })"
+			),
+		));
+	}
+
+	for trait_item in &api.traits {
+		rust_exports.push(generate_rust_trait_vtable(api, trait_item));
+	}
+
+	for trait_item in api.traits.iter().filter(|t| t.c_implementable) {
+		rust_exports.push(generate_rust_trait_adapter(api, trait_item));
+	}
+
+	for (trait_item, struct_item) in api.exported_trait_impls() {
+		rust_exports.push(generate_rust_trait_impl_vtable(
+			api,
+			trait_item,
+			struct_item,
+		));
+	}
+
+	for func in &api.functions {
+		rust_exports.push(generate_rust_wrapper(api, func));
+	}
+
+	format!(
+		"// Conversions and vtables are emitted for every exported type, whether\n// or not a function ends up using them\n#![allow(dead_code)]\n\nextern crate alloc;\n\nuse alloc::boxed::Box;\n{}",
+		rust_exports.join("\n")
+	)
+}
+
+// Generate the status codes returned by fallible functions and the
+// thread-local storage keeping the last error around for the caller
+fn generate_rust_error_handling(api: &Api) -> String {
+	let codes = STATUS_CODES
+		.iter()
+		.enumerate()
+		.map(|(value, (code, _))| format!("    {} = {},\n", code, value))
+		.collect::<String>();
+
+	format!(
+		r#"
+#[repr(i32)]
+#[derive(Clone, Copy)]
+pub enum Status {{
+{codes}}}
+
+std::thread_local! {{
+    static LAST_ERROR: core::cell::RefCell<Option<std::ffi::CString>> =
+        const {{ core::cell::RefCell::new(None) }};
+}}
+
+fn panic_message(panic: &(dyn core::any::Any + Send)) -> String {{
+    let message = panic
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| panic.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("unknown cause");
+    format!("Rust panicked: {{}}", message)
+}}
+
+fn set_last_error(error: impl core::fmt::Display) {{
+    // C strings end at the first NUL byte
+    let message = error.to_string().replace('\0', "\\0");
+    let message = std::ffi::CString::new(message).unwrap_or_default();
+    LAST_ERROR.with(|last_error| *last_error.borrow_mut() = Some(message));
+}}
+
+#[no_mangle]
+pub extern "C" fn {prefix}_last_error_message() -> *const core::ffi::c_char {{
+    LAST_ERROR.with(|last_error| {{
+        last_error
+            .borrow()
+            .as_ref()
+            .map_or(core::ptr::null(), |message| message.as_ptr())
+    }})
+}}
+
+#[no_mangle]
+pub extern "C" fn {prefix}_last_error_length() -> usize {{
+    LAST_ERROR.with(|last_error| {{
+        last_error
+            .borrow()
+            .as_ref()
+            .map_or(0, |message| message.as_bytes().len())
+    }})
+}}
+
+#[no_mangle]
+pub extern "C" fn {prefix}_clear_last_error() {{
+    LAST_ERROR.with(|last_error| *last_error.borrow_mut() = None);
+}}
+"#,
+		prefix = api.crate_name.to_case(Case::Snake),
+	)
+}
+
+// Generate the function releasing strings handed to C and the cache of
+// NUL-terminated copies of `&'static str`s, which C borrows
+fn generate_rust_strings(api: &Api) -> String {
+	format!(
+		r#"
+#[no_mangle]
+pub extern "C" fn {free}(string: *mut core::ffi::c_char) {{
+    if !string.is_null() {{
+        drop(unsafe {{ std::ffi::CString::from_raw(string) }});
+    }}
+}}
+
+// Each distinct string is copied once and kept for the rest of the program
+fn static_c_str(
+    value: &'static str,
+) -> Result<*const core::ffi::c_char, std::ffi::NulError> {{
+    static STRINGS: std::sync::Mutex<
+        std::collections::BTreeMap<(usize, usize), &'static core::ffi::CStr>,
+    > = std::sync::Mutex::new(std::collections::BTreeMap::new());
+
+    let mut strings = STRINGS
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+    let key = (value.as_ptr() as usize, value.len());
+    if let Some(string) = strings.get(&key) {{
+        return Ok(string.as_ptr());
+    }}
+    let string: &'static core::ffi::CStr =
+        Box::leak(std::ffi::CString::new(value)?.into_boxed_c_str());
+    strings.insert(key, string);
+    Ok(string.as_ptr())
+}}
+"#,
+		free = string_free_name(api),
+	)
+}
+
+// Generate the Rust extern "C" wrapper for a Rust function
+fn generate_rust_wrapper(api: &Api, func: &Function) -> String {
+	format!(
+		r#"
+#[no_mangle]
+pub extern "C" fn {func_name}({c_args}){ret_type} {{
+{body}}}
+"#,
+		func_name = func.c_name(api),
+		c_args = extern_c_params(
+			api,
+			&func.inputs,
+			&func.output,
+			api.utf8_policy(func)
+		),
+		ret_type = extern_c_return(api, &func.output),
+		body = catch_panics(
+			&generate_rust_call(
+				api,
+				&function_path(api, func),
+				None,
+				&func.inputs,
+				&func.output,
+				!func.options.assume_nonnull,
+				api.utf8_policy(func),
+			),
+			&panic_value(func),
+		),
+	)
+}
+
+// Run a wrapper body inside `catch_unwind`, since unwinding into C is
+// undefined behaviour, returning `panic_value` once the panic is recorded
+fn catch_panics(body: &str, panic_value: &str) -> String {
+	format!(
+		"    let call = std::panic::AssertUnwindSafe(|| {{\n{body}    }});\n    match std::panic::catch_unwind(call) {{\n        Ok(result) => result,\n        Err(panic) => {{\n            set_last_error(panic_message(panic.as_ref()));\n            {panic_value}\n        }}\n    }}\n"
+	)
+}
+
+// Value a wrapper returns to C when the function panics
+fn panic_value(func: &Function) -> String {
+	match (&func.options.panic_value, &func.output) {
+		(Some(value), _) => value.clone(),
+		(None, TypeRef::Unit) => "()".to_string(),
+		(None, TypeRef::Result { .. }) => "Status::Panic".to_string(),
+		(None, _) => "unsafe { core::mem::zeroed() }".to_string(),
+	}
+}
+
+// Parameter list of an extern "C" function
+fn extern_c_params(
+	api: &Api,
+	inputs: &[Param],
+	output: &TypeRef,
+	utf8: Utf8Policy,
+) -> String {
+	inputs
+		.iter()
+		.flat_map(|param| extern_c_param(api, param, utf8))
+		.chain(out_param(api, inputs, output))
+		.collect::<Vec<_>>()
+		.join(", ")
+}
+
+// extern "C" parameters standing for a Rust parameter; slices, and strings
+// under the `length` UTF-8 policy, take a pointer and a length
+fn extern_c_param(api: &Api, param: &Param, utf8: Utf8Policy) -> Vec<String> {
+	let name = identifier(&param.name);
+	let len = format!("{}: usize", slice_len_name(&param.name));
+	match &param.ty {
+		TypeRef::Slice { .. } => vec![
+			format!("{}: {}", name, rust_type_to_rust_extern_c(api, &param.ty)),
+			len,
+		],
+		ty if takes_length(ty, utf8) => {
+			vec![format!("{}: *const core::ffi::c_char", name), len]
+		}
+		ty => vec![format!(
+			"{}: {}",
+			name,
+			param_type_to_rust_extern_c(api, ty)
+		)],
+	}
+}
+
+// The out-parameter a fallible function writes its result through
+fn out_param(api: &Api, inputs: &[Param], output: &TypeRef) -> Option<String> {
+	let value = output.out_value()?;
+	Some(format!(
+		"{}: *mut {}",
+		out_param_name(inputs),
+		return_type_to_rust_extern_c(api, value)
+	))
+}
+
+// Return type annotation of an extern "C" function, empty for `()`
+fn extern_c_return(api: &Api, output: &TypeRef) -> String {
+	match output {
+		TypeRef::Unit => String::new(),
+		ty => format!(" -> {}", return_type_to_rust_extern_c(api, ty)),
+	}
+}
+
+// Map returned types to extern "C" types; borrowed strings are handed to C as
+// owned copies
+fn return_type_to_rust_extern_c(api: &Api, ty: &TypeRef) -> String {
+	match ty {
+		TypeRef::Str => "*mut core::ffi::c_char".to_string(),
+		TypeRef::Option(inner) if **inner == TypeRef::Str => {
+			"*mut core::ffi::c_char".to_string()
+		}
+		_ => rust_type_to_rust_extern_c(api, ty),
+	}
+}
+
+// Convert the extern "C" arguments, call the Rust function and convert its
+// result back; `receiver` is an already converted leading argument
+fn generate_rust_call(
+	api: &Api,
+	call_path: &str,
+	receiver: Option<&str>,
+	inputs: &[Param],
+	output: &TypeRef,
+	null_checks: bool,
+	utf8: Utf8Policy,
+) -> String {
+	let mut rust_call = String::new();
+	let on_error = error_return(output, "Error");
+
+	// Pointers are all checked before anything is taken ownership of
+	if null_checks {
+		let on_null = error_return(output, "NullPointer");
+		for param in inputs.iter().filter(|p| {
+			is_pointer_param(api, &p.ty) && !takes_length(&p.ty, utf8)
+		}) {
+			rust_call.push_str(&null_check(
+				&identifier(&param.name),
+				&c_identifier(&param.name),
+				&on_null,
+			));
+		}
+		// Empty slices and strings may come as NULL
+		for param in inputs.iter().filter(|p| match &p.ty {
+			TypeRef::Slice { .. } => true,
+			TypeRef::Option(_) => false,
+			ty => takes_length(ty, utf8),
+		}) {
+			rust_call.push_str(&format!(
+				"    if {arg_name}.is_null() && {len} != 0 {{\n        set_last_error(\"`{c_name}` is NULL but `{len}` is not 0\");\n        {on_null}\n    }}\n",
+				arg_name = identifier(&param.name),
+				c_name = c_identifier(&param.name),
+				len = slice_len_name(&param.name),
+			));
+		}
+		if output.out_value().is_some() {
+			let out_name = out_param_name(inputs);
+			rust_call.push_str(&null_check(&out_name, &out_name, &on_null));
+		}
+	}
+
+	for param in inputs {
+		let arg_name = identifier(&param.name);
+		rust_call.push_str(&if param.ty.is_string_from_c() {
+			convert_string_argument(&arg_name, &param.ty, utf8, &on_error)
+		} else {
+			convert_argument(api, &arg_name, &param.ty, utf8, &on_error)
+		});
+	}
+
+	let binding = match output {
+		TypeRef::Unit => "",
+		_ => "let result = ",
+	};
+	rust_call.push_str(&format!(
+		"    {binding}{call_path}({args});\n",
+		args = receiver
+			.map(str::to_string)
+			.into_iter()
+			.chain(inputs.iter().map(|param| call_argument(
+				api,
+				&identifier(&param.name),
+				&param.ty
+			)))
+			.collect::<Vec<_>>()
+			.join(", "),
+	));
+
+	for param in inputs {
+		rust_call.push_str(&write_back_argument(
+			api,
+			&identifier(&param.name),
+			&param.ty,
+		));
+	}
+
+	rust_call.push_str(&convert_result(api, output, &out_param_name(inputs)));
+	rust_call
+}
+
+// Generate the `#[repr(C)]` vtable for a trait, the boxed `dyn Trait` handle
+// and the vtable used for `Box<dyn Trait>` values returned to C
+fn generate_rust_trait_vtable(api: &Api, trait_item: &Trait) -> String {
+	let trait_path = trait_item.rust_path();
+	let entries = trait_item
+		.methods
+		.iter()
+		.map(|method| {
+			format!(
+				"    pub {}: extern \"C\" fn({}){},\n",
+				method.name,
+				vtable_entry_params(api, method, ""),
+				extern_c_return(api, &method.output)
+			)
+		})
+		.collect::<String>();
+
+	format!(
+		r#"
+#[repr(C)]
+pub struct {mirror}Vtable {{
+{entries}    pub free: extern "C" fn(*mut core::ffi::c_void),
+}}
+
+#[repr(C)]
+pub struct {mirror}Dyn {{
+    pub self_: *mut core::ffi::c_void,
+    pub vtable: *const {mirror}Vtable,
+}}
+{vtable}"#,
+		mirror = c_mirror_name(&trait_item.name),
+		vtable = generate_rust_vtable_instance(
+			api,
+			trait_item,
+			&format!("{}_dyn", trait_item.name.to_case(Case::Snake)),
+			&format!("Box<dyn {}>", trait_path),
+			&format!("dyn {}", trait_path),
+			true,
+		),
+	)
+}
+
+// Generate the callback table C code fills in to implement a trait and the
+// adapter implementing the Rust trait on top of it
+fn generate_rust_trait_adapter(api: &Api, trait_item: &Trait) -> String {
+	let mirror = c_mirror_name(&trait_item.name);
+	let mut entries = String::new();
+	let mut methods = String::new();
+
+	for method in &trait_item.methods {
+		let params = &method.inputs[1..];
+		entries.push_str(&format!(
+			"    pub {}: Option<extern \"C\" fn({}){}>,\n",
+			method.name,
+			std::iter::once("*mut core::ffi::c_void".to_string())
+				.chain(params.iter().map(|param| {
+					callback_type_to_rust_extern_c(api, &param.ty)
+				}))
+				.collect::<Vec<_>>()
+				.join(", "),
+			match &method.output {
+				TypeRef::Unit => String::new(),
+				ty =>
+					format!(" -> {}", callback_type_to_rust_extern_c(api, ty)),
+			}
+		));
+
+		let receiver = match method.inputs[0].ty {
+			TypeRef::Reference { mutable: true, .. } => "&mut self",
+			_ => "&self",
+		};
+		let signature = std::iter::once(receiver.to_string())
+			.chain(params.iter().map(|param| {
+				format!(
+					"{}: {}",
+					identifier(&param.name),
+					rust_type_name(api, &param.ty)
+				)
+			}))
+			.collect::<Vec<_>>()
+			.join(", ");
+		let mut body = params
+			.iter()
+			.map(|param| callback_argument(&identifier(&param.name), &param.ty))
+			.collect::<String>();
+		let args = std::iter::once("self.callbacks.user_data".to_string())
+			.chain(params.iter().map(|param| {
+				callback_call_argument(api, &identifier(&param.name), &param.ty)
+			}))
+			.collect::<Vec<_>>()
+			.join(", ");
+		body.push_str(&format!(
+			"        let Some(callback) = self.callbacks.{name} else {{\n            unreachable!(\"checked when the adapter was built\");\n        }};\n",
+			name = method.name,
+		));
+		body.push_str(&match &method.output {
+			TypeRef::Unit => format!("        callback({args});\n"),
+			ty => format!(
+				"        let result = callback({args});\n{}",
+				callback_result(api, &method.name, ty)
+			),
+		});
+
+		methods.push_str(&format!(
+			"    fn {name}({signature}){ret_type} {{\n{body}    }}\n",
+			name = method.name,
+			ret_type = match &method.output {
+				TypeRef::Unit => String::new(),
+				ty => format!(" -> {}", rust_type_name(api, ty)),
+			},
+		));
+	}
+
+	format!(
+		r#"
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct {mirror}Callbacks {{
+    pub user_data: *mut core::ffi::c_void,
+{entries}    pub free: Option<extern "C" fn(*mut core::ffi::c_void)>,
+}}
+
+pub struct {name}Adapter {{
+    callbacks: {mirror}Callbacks,
+    // Only implementations handed over to Rust release `user_data`
+    owned: bool,
+}}
+
+impl {name}Adapter {{
+    // C keeps `user_data` when the table is rejected
+    fn owned(callbacks: {mirror}Callbacks) -> Result<Self, String> {{
+        Self::check(&callbacks)?;
+        Ok(Self {{ callbacks, owned: true }})
+    }}
+
+    fn borrowed(callbacks: {mirror}Callbacks) -> Result<Self, String> {{
+        Self::check(&callbacks)?;
+        Ok(Self {{ callbacks, owned: false }})
+    }}
+
+    // Every method needs its callback, only `free` may be NULL
+    fn check(callbacks: &{mirror}Callbacks) -> Result<(), String> {{
+{checks}        Ok(())
+    }}
+}}
+
+// C promises its callbacks can be called from any thread
+unsafe impl Send for {name}Adapter {{}}
+unsafe impl Sync for {name}Adapter {{}}
+
+impl {trait_path} for {name}Adapter {{
+{methods}}}
+
+impl Drop for {name}Adapter {{
+    fn drop(&mut self) {{
+        if let (true, Some(free)) = (self.owned, self.callbacks.free) {{
+            free(self.callbacks.user_data);
+        }}
+    }}
+}}
+"#,
+		name = trait_item.name,
+		trait_path = trait_item.rust_path(),
+		checks = if trait_item.methods.is_empty() {
+			"        let _ = callbacks;\n".to_string()
+		} else {
+			trait_item
+				.methods
+				.iter()
+				.map(|method| format!(
+					"        if callbacks.{name}.is_none() {{\n            return Err(\"the `{c_name}` callback is NULL\".to_string());\n        }}\n",
+					name = method.name,
+					c_name = c_identifier(&method.name),
+				))
+				.collect::<String>()
+		},
+	)
+}
+
+// Prepare a Rust argument before handing it to a C callback; C strings end
+// at the first NUL byte, so interior ones are passed escaped as `\0`
+fn callback_argument(arg_name: &str, ty: &TypeRef) -> String {
+	match ty {
+		TypeRef::String | TypeRef::Str => format!(
+			"        let {arg_name} = std::ffi::CString::new({arg_name}.replace('\\0', \"\\\\0\")).unwrap_or_default();\n",
+		),
+		_ => String::new(),
+	}
+}
+
+// Expression passed to a C callback for a prepared argument
+fn callback_call_argument(api: &Api, arg_name: &str, ty: &TypeRef) -> String {
+	match ty {
+		TypeRef::String | TypeRef::Str => format!("{}.as_ptr()", arg_name),
+		TypeRef::Named(name) if api.find_enum(name).is_some() => {
+			enum_to_c(api, name, arg_name)
+		}
+		TypeRef::Reference { mutable, .. } => {
+			let mutability = if *mutable { "mut" } else { "const" };
+			format!("{} as *{} _", arg_name, mutability)
+		}
+		_ => arg_name.to_string(),
+	}
+}
+
+// Convert the value returned by a C callback back into Rust
+fn callback_result(api: &Api, method_name: &str, ty: &TypeRef) -> String {
+	match ty {
+		TypeRef::String => format!(
+			"        assert!(!result.is_null(), \"`{method_name}` callback returned NULL\");\n        unsafe {{ core::ffi::CStr::from_ptr(result) }}.to_string_lossy().into_owned()\n",
+		),
+		TypeRef::Named(name) if api.find_enum(name).is_some() => format!(
+			"        {}.expect(\"invalid `{name}` value\")\n",
+			enum_from_c(api, name, "result", api.utf8),
+		),
+		_ => "        result\n".to_string(),
+	}
+}
+
+// Generate the vtable exposing a struct's implementation of a trait
+fn generate_rust_trait_impl_vtable(
+	api: &Api,
+	trait_item: &Trait,
+	struct_item: &Struct,
+) -> String {
+	let struct_path = struct_item.rust_path();
+	let prefix = format!(
+		"{}_{}",
+		api.c_type_name(&struct_item.name),
+		trait_item.name.to_case(Case::Snake)
+	);
+
+	format!(
+		r#"{vtable}
+#[no_mangle]
+pub extern "C" fn {prefix}_vtable() -> *const {mirror}Vtable {{
+    &{static_name}
+}}
+"#,
+		vtable = generate_rust_vtable_instance(
+			api,
+			trait_item,
+			&prefix,
+			&struct_path,
+			&struct_path,
+			!struct_item.by_value,
+		),
+		mirror = c_mirror_name(&trait_item.name),
+		static_name = prefix.to_case(Case::UpperSnake),
+	)
+}
+
+// Generate a static vtable whose `self` pointers point to `object_type`,
+// with one thunk per method calling `<self_type as Trait>::method`; `free`
+// only releases boxed objects, as `#[repr(C)]` values belong to C
+fn generate_rust_vtable_instance(
+	api: &Api,
+	trait_item: &Trait,
+	prefix: &str,
+	object_type: &str,
+	self_type: &str,
+	boxed: bool,
+) -> String {
+	let trait_path = trait_item.rust_path();
+	let mut thunks = String::new();
+	let mut entries = String::new();
+
+	for method in &trait_item.methods {
+		let mutable = matches!(
+			method.inputs[0].ty,
+			TypeRef::Reference { mutable: true, .. }
+		);
+		let (pointer, reference) = if mutable {
+			("*mut", "&mut ")
+		} else {
+			("*const", "&")
+		};
+		// Boxed trait objects need an extra dereference to reach the value
+		let deref = if object_type == self_type { "*" } else { "**" };
+
+		thunks.push_str(&format!(
+			r#"
+extern "C" fn {prefix}_{thunk_name}({params}){ret_type} {{
+{body}}}
+"#,
+			thunk_name = method.name.trim_start_matches("r#"),
+			params = vtable_entry_params(api, method, "self_: "),
+			ret_type = extern_c_return(api, &method.output),
+			body = catch_panics(
+				&format!(
+					"{}    let self_ = unsafe {{ {reference}{deref}(self_ as {pointer} {object_type}) }};\n{}",
+					if method.options.assume_nonnull {
+						String::new()
+					} else {
+						null_check(
+							"self_",
+							"self",
+							&error_return(&method.output, "NullPointer"),
+						)
+					},
+					generate_rust_call(
+						api,
+						&format!("<{} as {}>::{}", self_type, trait_path, method.name),
+						Some("self_"),
+						&method.inputs[1..],
+						&method.output,
+						!method.options.assume_nonnull,
+						api.utf8_policy(method),
+					)
+				),
+				&panic_value(method),
+			),
+		));
+		entries.push_str(&format!(
+			"    {method_name}: {prefix}_{thunk_name},\n",
+			method_name = method.name,
+			thunk_name = method.name.trim_start_matches("r#"),
+		));
+	}
+
+	format!(
+		r#"{thunks}
+extern "C" fn {prefix}_free(self_: *mut core::ffi::c_void) {{
+{free_body}}}
+
+static {static_name}: {mirror}Vtable = {mirror}Vtable {{
+{entries}    free: {prefix}_free,
+}};
+"#,
+		static_name = prefix.to_case(Case::UpperSnake),
+		mirror = c_mirror_name(&trait_item.name),
+		free_body = if boxed {
+			catch_panics(
+				&format!("    drop(unsafe {{ Box::from_raw(self_ as *mut {object_type}) }});\n"),
+				"()",
+			)
+		} else {
+			"    let _ = self_;\n".to_string()
+		},
+	)
+}
+
+// Parameters of a vtable function pointer: an untyped `self` followed by the
+// method's own parameters
+fn vtable_entry_params(
+	api: &Api,
+	method: &Function,
+	receiver_name: &str,
+) -> String {
+	let receiver = match method.inputs[0].ty {
+		TypeRef::Reference { mutable: true, .. } => "*mut core::ffi::c_void",
+		_ => "*const core::ffi::c_void",
+	};
+
+	std::iter::once(format!("{}{}", receiver_name, receiver))
+		.chain(method.inputs[1..].iter().flat_map(|param| {
+			extern_c_param(api, param, api.utf8_policy(method))
+		}))
+		.chain(out_param(api, &method.inputs[1..], &method.output))
+		.collect::<Vec<_>>()
+		.join(", ")
+}
+
+// Path used to call a function, going through the type for methods
+fn function_path(api: &Api, func: &Function) -> String {
+	match &func.self_type {
+		Some(self_type) => {
+			format!("{}::{}", named_type_path(api, self_type), func.rust_name())
+		}
+		None => func.rust_path(),
+	}
+}
+
+// Convert an extern "C" argument back into the Rust type the function expects
+fn convert_argument(
+	api: &Api,
+	arg_name: &str,
+	ty: &TypeRef,
+	utf8: Utf8Policy,
+	on_error: &str,
+) -> String {
+	match ty {
+		TypeRef::Primitive(_) => String::new(),
+		TypeRef::Char => format!(
+			"    let {arg_name} = {};\n",
+			unwrap_or_return(
+				&format!(
+					"char::from_u32({arg_name}).ok_or(\"`{arg_name}` is not a valid Unicode scalar value\")"
+				),
+				on_error,
+			),
+		),
+		// Enums arrive as C values and must be validated
+		TypeRef::Named(name) if api.find_enum(name).is_some() => format!(
+			"    let {arg_name} = {};\n",
+			unwrap_or_return(
+				&format!(
+					"{}.ok_or(\"`{arg_name}` is not a valid `{name}`\")",
+					enum_from_c(api, name, arg_name, utf8)
+				),
+				on_error,
+			),
+		),
+		TypeRef::Reference { mutable, ty } if is_enum_type(api, ty) => {
+			let TypeRef::Named(name) = &**ty else {
+				unreachable!()
+			};
+			format!(
+				"    let {mutability}{value} = {};\n",
+				unwrap_or_return(
+					&format!(
+						"{}.ok_or(\"`{arg_name}` does not point to a valid `{name}`\")",
+						enum_from_c(api, name, &format!("unsafe {{ *{arg_name} }}"), utf8)
+					),
+					on_error,
+				),
+				value = converted_value_name(arg_name),
+				mutability = if *mutable { "mut " } else { "" },
+			)
+		}
+		// `#[repr(C)]` structs are already Rust values
+		TypeRef::Named(name) if api.is_by_value_struct(name) => String::new(),
+		// Other structs passed by value are handed back to Rust
+		TypeRef::Named(_) => format!(
+			"    let {arg_name} = unsafe {{ *Box::from_raw({arg_name}) }};\n",
+		),
+		TypeRef::Boxed(inner) if is_struct_type(api, inner) => format!(
+			"    let {arg_name} = unsafe {{ Box::from_raw({arg_name}) }};\n",
+		),
+		TypeRef::Vec(inner) => format!(
+			"    let {arg_name} = unsafe {{ vec_{}_from_c({arg_name}) }};\n",
+			inner.vec_element_name().unwrap_or_default(),
+		),
+		TypeRef::Option(inner) => format!(
+			"    let {arg_name} = {};\n",
+			option_from_c(arg_name, inner)
+		),
+		// Trait objects are implemented by C callbacks
+		TypeRef::Boxed(inner) if is_trait_object(api, inner) => format!(
+			"    let {arg_name} = Box::new({});\n",
+			unwrap_or_return(
+				&format!("{}::owned({arg_name})", adapter_name(ty)),
+				on_error
+			),
+		),
+		TypeRef::ImplTrait(_) => format!(
+			"    let {arg_name} = {};\n",
+			unwrap_or_return(
+				&format!("{}::owned({arg_name})", adapter_name(ty)),
+				on_error
+			),
+		),
+		TypeRef::Reference { mutable, ty: inner }
+			if is_trait_object(api, inner) =>
+		{
+			format!(
+				"    let {mutability}{value} = {};\n",
+				unwrap_or_return(
+					&format!(
+						"{}::borrowed(unsafe {{ *{arg_name} }})",
+						adapter_name(ty)
+					),
+					on_error
+				),
+				value = converted_value_name(arg_name),
+				mutability = if *mutable { "mut " } else { "" },
+			)
+		}
+		TypeRef::Reference { mutable, ty }
+			if matches!(**ty, TypeRef::Named(_) | TypeRef::Primitive(_)) =>
+		{
+			let mutability = if *mutable { "mut " } else { "" };
+			format!(
+				"    let {arg_name} = unsafe {{ &{mutability}*{arg_name} }};\n"
+			)
+		}
+		// `from_raw_parts` needs a non-NULL pointer and a size that fits in
+		// an `isize`, even for empty slices
+		TypeRef::Slice { mutable, ty } => {
+			let (mutability, from_raw_parts) = if *mutable {
+				("mut ", "from_raw_parts_mut")
+			} else {
+				("", "from_raw_parts")
+			};
+			format!(
+				"    if {len} > isize::MAX as usize / core::mem::size_of::<{element}>().max(1) {{\n        set_last_error(\"`{len}` is too large\");\n        {on_error}\n    }}\n    let {arg_name}: &{mutability}[{element}] = if {len} == 0 {{\n        &{mutability}[]\n    }} else {{\n        unsafe {{ core::slice::{from_raw_parts}({arg_name}, {len}) }}\n    }};\n",
+				element = rust_type_name(api, ty),
+				len = slice_len_name(arg_name.trim_end_matches('_')),
+			)
+		}
+		// Every generated function only takes parameters passing
+		// `Api::is_c_param`, which rejects every other type
+		_ => unreachable!("{:?} can't be passed from C", ty),
+	}
+}
+
+// Expression unwrapping the `Result` `value`, recording the error and
+// running `on_error` when it fails
+fn unwrap_or_return(value: &str, on_error: &str) -> String {
+	format!(
+		"match {value} {{ Ok(value) => value, Err(error) => {{ set_last_error(error); {on_error} }} }}"
+	)
+}
+
+// Statement leaving a wrapper early once an error has been recorded,
+// returning `status` from functions that return status codes
+fn error_return(output: &TypeRef, status: &str) -> String {
+	match output {
+		TypeRef::Unit => "return;".to_string(),
+		TypeRef::Result { .. } => format!("return Status::{};", status),
+		// Every other extern "C" type has a zero value: 0, false or NULL
+		_ => "return unsafe { core::mem::zeroed() };".to_string(),
+	}
+}
+
+// Reject a NULL pointer argument before it is dereferenced, naming it the
+// way the C prototype does
+fn null_check(arg_name: &str, c_name: &str, on_null: &str) -> String {
+	format!(
+		"    if {arg_name}.is_null() {{\n        set_last_error(\"`{c_name}` is NULL\");\n        {on_null}\n    }}\n"
+	)
+}
+
+// Whether a parameter reaches Rust as a pointer that must not be NULL
+fn is_pointer_param(api: &Api, ty: &TypeRef) -> bool {
+	match ty {
+		TypeRef::String | TypeRef::Str | TypeRef::Reference { .. } => true,
+		TypeRef::Named(name) => api.is_opaque_struct(name),
+		TypeRef::Boxed(inner) => is_struct_type(api, inner),
+		_ => false,
+	}
+}
+
+// Expression converting an `Option` argument, where NULL or a cleared
+// presence flag stand for `None`
+fn option_from_c(arg_name: &str, inner: &TypeRef) -> String {
+	match inner {
+		TypeRef::Primitive(_) => {
+			format!("{arg_name}.has_value.then_some({arg_name}.value)")
+		}
+		TypeRef::Reference { mutable: true, .. } => {
+			format!("unsafe {{ {arg_name}.as_mut() }}")
+		}
+		TypeRef::Reference { .. } => {
+			format!("unsafe {{ {arg_name}.as_ref() }}")
+		}
+		TypeRef::Boxed(_) => format!(
+			"(!{arg_name}.is_null()).then(|| unsafe {{ Box::from_raw({arg_name}) }})"
+		),
+		// Structs passed by value
+		_ => format!(
+			"(!{arg_name}.is_null()).then(|| unsafe {{ *Box::from_raw({arg_name}) }})"
+		),
+	}
+}
+
+// Statements decoding a string argument, possibly an `Option`, according to
+// the UTF-8 policy
+fn convert_string_argument(
+	arg_name: &str,
+	ty: &TypeRef,
+	utf8: Utf8Policy,
+	on_error: &str,
+) -> String {
+	let (optional, value_ty) = match ty {
+		TypeRef::Option(inner) => (true, &**inner),
+		ty => (false, ty),
+	};
+	let len = slice_len_name(arg_name.trim_end_matches('_'));
+
+	let mut conversion = String::new();
+	let bytes = if utf8 == Utf8Policy::Length {
+		// `from_raw_parts` needs a non-NULL pointer and a size that fits in
+		// an `isize`, even for empty strings
+		conversion.push_str(&format!(
+			"    if {len} > isize::MAX as usize {{\n        set_last_error(\"`{len}` is too large\");\n        {on_error}\n    }}\n"
+		));
+		format!(
+			"if {len} == 0 {{ &[][..] }} else {{ unsafe {{ core::slice::from_raw_parts({arg_name}.cast::<u8>(), {len}) }} }}"
+		)
+	} else {
+		format!("unsafe {{ core::ffi::CStr::from_ptr({arg_name}) }}.to_bytes()")
+	};
+	let text = if utf8 == Utf8Policy::Lossy {
+		format!("String::from_utf8_lossy({bytes})")
+	} else {
+		unwrap_or_return(
+			&format!("core::str::from_utf8({bytes}).map_err(|error| format!(\"`{arg_name}` is not valid UTF-8: {{}}\", error))"),
+			on_error,
+		)
+	};
+	let value = match (value_ty, utf8) {
+		(TypeRef::String, Utf8Policy::Lossy) => format!("{text}.into_owned()"),
+		(TypeRef::String, _) => format!("{text}.to_string()"),
+		_ => text,
+	};
+
+	// Invalid UTF-8 returns early, which a closure can't do
+	if optional {
+		conversion.push_str(&format!(
+			"    let {arg_name} = if {arg_name}.is_null() {{ None }} else {{ Some({value}) }};\n"
+		));
+	} else {
+		conversion.push_str(&format!("    let {arg_name} = {value};\n"));
+	}
+	// Lossy decoding only borrows when the bytes were valid
+	if *value_ty == TypeRef::Str && utf8 == Utf8Policy::Lossy {
+		conversion.push_str(&if optional {
+			format!("    let {arg_name} = {arg_name}.as_deref();\n")
+		} else {
+			format!("    let {arg_name}: &str = &{arg_name};\n")
+		});
+	}
+	conversion
+}
+
+// Local holding an argument converted from C, e.g. for a `&Enum` parameter
+fn converted_value_name(arg_name: &str) -> String {
+	format!("{}_value", arg_name.trim_end_matches('_'))
+}
+
+// Expression passed to the Rust function for a converted argument
+fn call_argument(api: &Api, arg_name: &str, ty: &TypeRef) -> String {
+	match ty {
+		TypeRef::Reference { mutable: true, ty }
+			if is_enum_type(api, ty) || is_trait_object(api, ty) =>
+		{
+			format!("&mut {}", converted_value_name(arg_name))
+		}
+		TypeRef::Reference { ty, .. }
+			if is_enum_type(api, ty) || is_trait_object(api, ty) =>
+		{
+			format!("&{}", converted_value_name(arg_name))
+		}
+		_ => arg_name.to_string(),
+	}
+}
+
+// Copy values the Rust function may have changed back to the caller
+fn write_back_argument(api: &Api, arg_name: &str, ty: &TypeRef) -> String {
+	match ty {
+		TypeRef::Reference { mutable: true, ty } if is_enum_type(api, ty) => {
+			let TypeRef::Named(name) = &**ty else {
+				unreachable!()
+			};
+			format!(
+				"    unsafe {{ *{arg_name} = {to_c} }};\n",
+				to_c = enum_to_c(api, name, &converted_value_name(arg_name)),
+			)
+		}
+		_ => String::new(),
+	}
+}
+
+// Convert the Rust function's result into its extern "C" representation
+fn convert_result(api: &Api, ty: &TypeRef, out_name: &str) -> String {
+	let on_error = error_return(ty, "Error");
+	match ty {
+		TypeRef::Unit => String::new(),
+		// Errors are kept for the caller to inspect
+		TypeRef::Result { ok, err } => {
+			let write_out = match ty.out_value() {
+				Some(_) => format!(
+					"            unsafe {{ *{out_name} = {} }};\n",
+					result_to_c(api, ok, "value", &on_error)
+				),
+				None => String::new(),
+			};
+			format!(
+				"    match result {{\n        Ok({binding}) => {{\n{write_out}            Status::Ok\n        }}\n        Err({error}) => {{\n            set_last_error({message});\n            Status::Error\n        }}\n    }}\n",
+				binding = if write_out.is_empty() { "_" } else { "value" },
+				error = if formats(api, err, "Display") || formats(api, err, "Debug") {
+					"error"
+				} else {
+					"_"
+				},
+				message = error_message(api, err),
+			)
+		}
+		ty => format!("    {}\n", result_to_c(api, ty, "result", &on_error)),
+	}
+}
+
+// Expression for the message recorded when a call fails with `error`,
+// preferring the error type's `Display` over its `Debug` output
+fn error_message(api: &Api, err: &TypeRef) -> String {
+	if formats(api, err, "Display") {
+		"error".to_string()
+	} else if formats(api, err, "Debug") {
+		"format!(\"{:?}\", error)".to_string()
+	} else {
+		"\"the call failed\"".to_string()
+	}
+}
+
+// Whether values of type `ty` implement the formatting trait `trait_name`
+fn formats(api: &Api, ty: &TypeRef, trait_name: &str) -> bool {
+	match ty {
+		TypeRef::Primitive(_)
+		| TypeRef::Char
+		| TypeRef::String
+		| TypeRef::Str
+		| TypeRef::StaticStr => true,
+		TypeRef::Named(name) => api.implements(name, trait_name),
+		// `std::error::Error` requires both, and types from other crates
+		// returned as errors are expected to implement it
+		TypeRef::DynTrait(name) => name == "Error",
+		TypeRef::Unsupported(_) => true,
+		TypeRef::Boxed(inner) | TypeRef::Reference { ty: inner, .. } => {
+			formats(api, inner, trait_name)
+		}
+		TypeRef::Vec(inner) | TypeRef::Option(inner) => {
+			trait_name == "Debug" && formats(api, inner, trait_name)
+		}
+		_ => false,
+	}
+}
+
+// Expression converting a Rust value into its extern "C" representation
+fn result_to_c(api: &Api, ty: &TypeRef, value: &str, on_error: &str) -> String {
+	match ty {
+		TypeRef::Named(name) if api.is_tagged_union(name) => {
+			unwrap_or_return(&enum_to_c(api, name, value), on_error)
+		}
+		TypeRef::Named(name) if api.is_c_enum(name) => {
+			enum_to_c(api, name, value)
+		}
+		TypeRef::String | TypeRef::Str => format!(
+			"{}.into_raw()",
+			unwrap_or_return(
+				&format!("std::ffi::CString::new({value})"),
+				on_error
+			)
+		),
+		TypeRef::StaticStr => unwrap_or_return(
+			&format!("static_c_str({value})"),
+			on_error,
+		),
+		TypeRef::Named(name) if api.is_by_value_struct(name) => value.to_string(),
+		TypeRef::Named(_) => format!("Box::into_raw(Box::new({value}))"),
+		TypeRef::Option(inner) if inner.option_value_name().is_some() => format!(
+			"{{ let value = {value}; {mirror} {{ has_value: value.is_some(), value: value.unwrap_or_default() }} }}",
+			mirror = option_mirror_name(inner),
+		),
+		TypeRef::Option(inner) => format!(
+			"match {value} {{ Some(value) => {some}, None => core::ptr::{null}() }}",
+			some = result_to_c(api, inner, "value", on_error),
+			null = if return_type_to_rust_extern_c(api, inner).starts_with("*const") {
+				"null"
+			} else {
+				"null_mut"
+			},
+		),
+		TypeRef::Vec(inner) if **inner == TypeRef::String => {
+			unwrap_or_return(&format!("vec_string_to_c({value})"), on_error)
+		}
+		TypeRef::Vec(inner) => format!(
+			"vec_{}_to_c({value})",
+			inner.vec_element_name().unwrap_or_default()
+		),
+		TypeRef::Boxed(inner) if is_struct_type(api, inner) => {
+			format!("Box::into_raw({value})")
+		}
+		// The trait object is boxed once more to get a thin pointer for C
+		TypeRef::Boxed(inner) if is_trait_object(api, inner) => {
+			let TypeRef::DynTrait(trait_name) = &**inner else {
+				unreachable!()
+			};
+			format!(
+				"{mirror}Dyn {{ self_: Box::into_raw(Box::new({value})) as *mut core::ffi::c_void, vtable: &{static_name}_DYN }}",
+				mirror = c_mirror_name(trait_name),
+				static_name = trait_name.to_case(Case::UpperSnake),
+			)
+		}
+		TypeRef::Reference { mutable, ty }
+			if matches!(**ty, TypeRef::Named(_) | TypeRef::Primitive(_)) =>
+		{
+			let mutability = if *mutable { "mut" } else { "const" };
+			format!("{value} as *{mutability} _")
+		}
+		TypeRef::Iterator(item) => format!(
+			"Box::into_raw(Box::new({}(Box::new({value}))))",
+			iterator_mirror_name(api, item)
+		),
+		TypeRef::Primitive(_) => value.to_string(),
+		TypeRef::Char => format!("u32::from({value})"),
+		// `check_api` rejects every other returned type
+		_ => unreachable!("{:?} can't be returned to C", ty),
+	}
+}
+
+// Generate the checked conversion from a C integer to a fieldless enum
+fn generate_rust_enum_conversion(enum_item: &Enum) -> String {
+	let enum_path = enum_item.rust_path();
+	let arms = enum_item
+		.variants
+		.iter()
+		.map(|variant| {
+			format!(
+				"        {} => Some({}::{}),\n",
+				variant.discriminant, enum_path, variant.name
+			)
+		})
+		.collect::<String>();
+	let to_c_arms = enum_item
+		.variants
+		.iter()
+		.map(|variant| {
+			format!(
+				"        {}::{} => {},\n",
+				enum_path, variant.name, variant.discriminant
+			)
+		})
+		.collect::<String>();
+
+	format!(
+		r#"
+fn {from_c}(value: i32) -> Option<{enum_path}> {{
+    match value {{
+{arms}        _ => None,
+    }}
+}}
+
+// Reads the C value through a reference, for enums that aren't `Copy`
+fn {to_c}(value: &{enum_path}) -> i32 {{
+    match value {{
+{to_c_arms}    }}
+}}
+"#,
+		from_c = enum_from_c_fn(&enum_item.name),
+		to_c = enum_to_c_fn(&enum_item.name),
+	)
+}
+
+// Name of the generated C integer -> enum conversion function
+fn enum_from_c_fn(enum_name: &str) -> String {
+	format!("{}_from_c", enum_name.to_case(Case::Snake))
+}
+
+// Name of the generated enum -> C value conversion function
+fn enum_to_c_fn(enum_name: &str) -> String {
+	format!("{}_to_c", enum_name.to_case(Case::Snake))
+}
+
+// Expression converting a C enum or tagged union value into `Option<Enum>`,
+// decoding payload strings according to the UTF-8 policy
+fn enum_from_c(
+	api: &Api,
+	enum_name: &str,
+	value: &str,
+	utf8: Utf8Policy,
+) -> String {
+	if api.is_c_enum(enum_name) {
+		format!("{}({})", enum_from_c_fn(enum_name), value)
+	} else {
+		format!(
+			"{}(&{}, {})",
+			enum_from_c_fn(enum_name),
+			value,
+			utf8 == Utf8Policy::Lossy
+		)
+	}
+}
+
+// Expression converting an enum into its C value
+fn enum_to_c(api: &Api, enum_name: &str, value: &str) -> String {
+	if api.is_c_enum(enum_name) {
+		format!("{} as i32", value)
+	} else {
+		format!("{}({})", enum_to_c_fn(enum_name), value)
+	}
+}
+
+fn is_enum_type(api: &Api, ty: &TypeRef) -> bool {
+	matches!(ty, TypeRef::Named(name) if api.find_enum(name).is_some())
+}
+
+fn is_struct_type(api: &Api, ty: &TypeRef) -> bool {
+	matches!(ty, TypeRef::Named(name) if api.find_struct(name).is_some())
+}
+
+// Whether `ty` is `dyn Trait` for an exported trait
+fn is_trait_object(api: &Api, ty: &TypeRef) -> bool {
+	matches!(ty, TypeRef::DynTrait(name) if api.find_trait(name).is_some())
+}
+
+// Name of the adapter implementing a trait object's trait with C callbacks
+fn adapter_name(ty: &TypeRef) -> String {
+	format!("{}Adapter", ty.trait_object_name().unwrap_or_default())
+}
+
+// Name of the `#[repr(C)]` mirror type generated for a tagged union
+fn c_mirror_name(name: &str) -> String {
+	format!("C{}", name)
+}
+
+// Generate the `#[repr(C)]` tagged union mirroring a data-carrying enum,
+// conversions to and from the original enum and the function releasing the
+// strings a converted value owns
+fn generate_rust_tagged_union(api: &Api, enum_item: &Enum) -> String {
+	let enum_path = enum_item.rust_path();
+	let mirror = c_mirror_name(&enum_item.name);
+	let enum_name_c = enum_item.name.to_case(Case::Snake);
+
+	let mut rust_wrapper = String::new();
+	let mut union_members = String::new();
+	let mut to_c_arms = String::new();
+	let mut from_c_arms = String::new();
+	let mut release_arms = String::new();
+
+	for variant in &enum_item.variants {
+		let member = union_member_name(&variant.name);
+		let variant_mirror = format!("{}{}", mirror, variant.name);
+		let bindings = variant
+			.fields
+			.iter()
+			.enumerate()
+			.map(|(index, field)| field.name_or_index(index))
+			.collect::<Vec<_>>();
+		let pattern = if variant.fields.is_empty() {
+			String::new()
+		} else if variant.has_named_fields() {
+			format!(" {{ {} }}", bindings.join(", "))
+		} else {
+			format!("({})", bindings.join(", "))
+		};
+
+		if variant.fields.is_empty() {
+			to_c_arms.push_str(&format!(
+				"        {enum_path}::{variant_name} => Ok({mirror} {{ tag: {tag}, data: unsafe {{ core::mem::zeroed() }} }}),\n",
+				variant_name = variant.name,
+				tag = variant.discriminant,
+			));
+			from_c_arms.push_str(&format!(
+				"        {tag} => Some({enum_path}::{variant_name}),\n",
+				variant_name = variant.name,
+				tag = variant.discriminant,
+			));
+			continue;
+		}
+
+		let mut mirror_fields = String::new();
+		let mut to_c_fields = Vec::new();
+		let mut from_c_fields = Vec::new();
+		let mut release_fields = String::new();
+		for (field, binding) in variant.fields.iter().zip(&bindings) {
+			let field_name = identifier(binding);
+			mirror_fields.push_str(&format!(
+				"    pub {}: {},\n",
+				field_name,
+				rust_type_to_rust_extern_c(api, &field.ty)
+			));
+			to_c_fields.push(format!(
+				"{}: {}",
+				field_name,
+				payload_to_c(api, &field.ty, binding)
+			));
+			from_c_fields.push(payload_from_c(
+				api,
+				&field.ty,
+				&format!("data.{}", field_name),
+			));
+			release_fields.push_str(&release_payload(
+				api,
+				&field.ty,
+				&format!("data.{}", field_name),
+			));
+		}
+
+		rust_wrapper.push_str(&format!(
+			r#"
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct {variant_mirror} {{
+{mirror_fields}}}
+"#,
+		));
+		union_members
+			.push_str(&format!("    pub {member}: {variant_mirror},\n"));
+		to_c_arms.push_str(&format!(
+			"        {enum_path}::{variant_name}{pattern} => Ok({mirror} {{ tag: {tag}, data: {mirror}Data {{ {member}: {variant_mirror} {{ {fields} }} }} }}),\n",
+			variant_name = variant.name,
+			tag = variant.discriminant,
+			fields = to_c_fields.join(", "),
+		));
+
+		let construct = if variant.has_named_fields() {
+			format!(
+				"{{ {} }}",
+				bindings
+					.iter()
+					.zip(&from_c_fields)
+					.map(|(binding, value)| format!("{}: {}", binding, value))
+					.collect::<Vec<_>>()
+					.join(", ")
+			)
+		} else {
+			format!("({})", from_c_fields.join(", "))
+		};
+		from_c_arms.push_str(&format!(
+			"        {tag} => {{\n            let data = unsafe {{ value.data.{member} }};\n            Some({enum_path}::{variant_name}{construct})\n        }}\n",
+			variant_name = variant.name,
+			tag = variant.discriminant,
+		));
+
+		if !release_fields.is_empty() {
+			release_arms.push_str(&format!(
+				"        {tag} => {{\n            let data = unsafe {{ &mut value.data.{member} }};\n{release_fields}        }}\n",
+				tag = variant.discriminant,
+			));
+		}
+	}
+
+	// Strings, directly or in a nested tagged union, are all that's decoded
+	let decodes_strings = enum_item
+		.variants
+		.iter()
+		.flat_map(|variant| &variant.fields)
+		.any(|field| match &field.ty {
+			TypeRef::String => true,
+			TypeRef::Named(name) => api.is_tagged_union(name),
+			_ => false,
+		});
+	let release_body =
+		if release_arms.is_empty() {
+			"    let _ = value;\n".to_string()
+		} else {
+			format!("    match value.tag {{\n{release_arms}        _ => {{}}\n    }}\n")
+		};
+
+	rust_wrapper.push_str(&format!(
+		r#"
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub union {mirror}Data {{
+{union_members}}}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct {mirror} {{
+    pub tag: i32,
+    pub data: {mirror}Data,
+}}
+
+// Fails on strings with interior NUL bytes, which C can't represent
+fn {to_c}(value: {enum_path}) -> Result<{mirror}, std::ffi::NulError> {{
+    match value {{
+{to_c_arms}    }}
+}}
+
+// Payload strings are decoded like string arguments, replacing invalid UTF-8
+// when `lossy` is set and rejecting it otherwise
+fn {from_c}(value: &{mirror}, {lossy}: bool) -> Option<{enum_path}> {{
+    match value.tag {{
+{from_c_arms}        _ => None,
+    }}
+}}
+
+fn {enum_name_c}_release(value: &mut {mirror}) {{
+{release_body}}}
+
+#[no_mangle]
+pub extern "C" fn {enum_name_c}_free(value: *mut {mirror}) {{
+    if let Some(value) = unsafe {{ value.as_mut() }} {{
+        {enum_name_c}_release(value);
+    }}
+}}
+"#,
+		to_c = enum_to_c_fn(&enum_item.name),
+		from_c = enum_from_c_fn(&enum_item.name),
+		lossy = if decodes_strings { "lossy" } else { "_lossy" },
+	));
+
+	rust_wrapper
+}
+
+// Convert a Rust payload field into its tagged union representation
+fn payload_to_c(api: &Api, ty: &TypeRef, value: &str) -> String {
+	match ty {
+		TypeRef::String => {
+			format!("std::ffi::CString::new({})?.into_raw()", value)
+		}
+		TypeRef::Named(name) if api.is_tagged_union(name) => {
+			format!("{}?", enum_to_c(api, name, value))
+		}
+		TypeRef::Named(name) => enum_to_c(api, name, value),
+		_ => value.to_string(),
+	}
+}
+
+// Read a payload field back out of a tagged union, bailing out with `None`
+// on invalid values
+fn payload_from_c(api: &Api, ty: &TypeRef, value: &str) -> String {
+	match ty {
+		TypeRef::String => format!(
+			"{{ if {value}.is_null() {{ return None; }} let bytes = unsafe {{ core::ffi::CStr::from_ptr({value}) }}.to_bytes(); if lossy {{ String::from_utf8_lossy(bytes).into_owned() }} else {{ core::str::from_utf8(bytes).ok()?.to_string() }} }}"
+		),
+		TypeRef::Named(name) if api.is_tagged_union(name) => {
+			format!("{}(&{value}, lossy)?", enum_from_c_fn(name))
+		}
+		TypeRef::Named(name) => {
+			format!("{}({value})?", enum_from_c_fn(name))
+		}
+		_ => value.to_string(),
+	}
+}
+
+// Free whatever a payload field owns
+fn release_payload(api: &Api, ty: &TypeRef, value: &str) -> String {
+	match ty {
+		TypeRef::String => format!(
+			"            if !{value}.is_null() {{\n                drop(unsafe {{ std::ffi::CString::from_raw({value}) }});\n                {value} = core::ptr::null_mut();\n            }}\n"
+		),
+		TypeRef::Named(name) if api.is_tagged_union(name) => format!(
+			"            {}_release(&mut {value});\n",
+			api.c_type_name(name)
+		),
+		_ => String::new(),
+	}
+}
+
+// Generate the `#[repr(C)]` buffer type for a `Vec`, the conversions handing
+// a `Vec` over to C and taking it back, and the exported function freeing it
+fn generate_rust_vec(api: &Api, element: &TypeRef) -> String {
+	let mirror = vec_mirror_name(element);
+	let element_name = element.vec_element_name().unwrap_or_default();
+	let element_c = rust_type_to_rust_extern_c(api, element);
+
+	// Strings are converted one by one, the other elements are moved as is
+	let (to_c, from_c) = match element {
+		TypeRef::String => (
+			format!(
+				r#"fn vec_{element_name}_to_c(vec: Vec<String>) -> Result<{mirror}, std::ffi::NulError> {{
+    let strings = vec
+        .into_iter()
+        .map(std::ffi::CString::new)
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut vec = core::mem::ManuallyDrop::new(
+        strings
+            .into_iter()
+            .map(std::ffi::CString::into_raw)
+            .collect::<Vec<_>>(),
+    );
+    Ok({mirror} {{ data: vec.as_mut_ptr(), len: vec.len(), capacity: vec.capacity() }})
+}}"#
+			),
+			format!(
+				r#"unsafe fn vec_{element_name}_from_c(vec: {mirror}) -> Vec<String> {{
+    if vec.data.is_null() {{
+        return Vec::new();
+    }}
+    Vec::from_raw_parts(vec.data, vec.len, vec.capacity)
+        .into_iter()
+        .map(|string| {{
+            if string.is_null() {{
+                return String::new();
+            }}
+            std::ffi::CString::from_raw(string).to_string_lossy().into_owned()
+        }})
+        .collect()
+}}"#
+			),
+		),
+		_ => (
+			format!(
+				r#"fn vec_{element_name}_to_c(vec: Vec<{element_c}>) -> {mirror} {{
+    let mut vec = core::mem::ManuallyDrop::new(vec);
+    {mirror} {{ data: vec.as_mut_ptr(), len: vec.len(), capacity: vec.capacity() }}
+}}"#
+			),
+			format!(
+				r#"unsafe fn vec_{element_name}_from_c(vec: {mirror}) -> Vec<{element_c}> {{
+    if vec.data.is_null() {{
+        return Vec::new();
+    }}
+    Vec::from_raw_parts(vec.data, vec.len, vec.capacity)
+}}"#
+			),
+		),
+	};
+
+	format!(
+		r#"
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct {mirror} {{
+    pub data: *mut {element_c},
+    pub len: usize,
+    pub capacity: usize,
+}}
+
+{to_c}
+
+// The buffer must come from `vec_{element_name}_to_c`, C can't allocate one
+{from_c}
+
+#[no_mangle]
+pub extern "C" fn {name}_free(vec: *mut {mirror}) {{
+    if let Some(vec) = unsafe {{ vec.as_mut() }} {{
+        drop(unsafe {{ vec_{element_name}_from_c(*vec) }});
+        vec.data = core::ptr::null_mut();
+        vec.len = 0;
+        vec.capacity = 0;
+    }}
+}}
+"#,
+		name = vec_type(api, element),
+	)
+}
+
+// Generate the handle boxing an iterator returned to C, along with the
+// functions advancing and releasing it
+fn generate_rust_iterator(api: &Api, item: &TypeRef) -> String {
+	format!(
+		r#"
+pub struct {mirror}(Box<dyn Iterator<Item = {item_rust}>>);
+
+#[no_mangle]
+pub extern "C" fn {name}_next(iter: *mut {mirror}, out: *mut {item_c}) -> bool {{
+{next}}}
+
+#[no_mangle]
+pub extern "C" fn {name}_free(iter: *mut {mirror}) {{
+{free}}}
+"#,
+		mirror = iterator_mirror_name(api, item),
+		name = iterator_type(api, item),
+		item_rust = rust_type_name(api, item),
+		item_c = return_type_to_rust_extern_c(api, item),
+		next = iterator_next_body(api, item, "unsafe { &mut *iter }.0.next()"),
+		free = catch_panics(
+			"    if !iter.is_null() {\n        unsafe {\n            let _ = Box::from_raw(iter);\n        }\n    }\n",
+			"()",
+		),
+	)
+}
+
+// Body of an iterator's `_next`, pulling an item with `next` and writing it
+// to `out`; `false` stands for both the end of the sequence and errors
+fn iterator_next_body(api: &Api, item: &TypeRef, next: &str) -> String {
+	let on_error = "return false;";
+	catch_panics(
+		&format!(
+			"{}{}    match {next} {{\n        Some(value) => {{\n            unsafe {{ *out = {} }};\n            true\n        }}\n        None => false,\n    }}\n",
+			null_check("iter", "iter", on_error),
+			null_check("out", "out", on_error),
+			result_to_c(api, item, "value", on_error),
+		),
+		"false",
+	)
+}
+
+// Name of the struct boxing an iterator handed to C
+fn iterator_mirror_name(api: &Api, item: &TypeRef) -> String {
+	format!(
+		"CIterator{}",
+		item.iterator_item_name(api)
+			.unwrap_or_default()
+			.to_case(Case::Pascal)
+	)
+}
+
+// Name of the `#[repr(C)]` struct generated for an `Option` with a presence
+// flag
+fn option_mirror_name(value: &TypeRef) -> String {
+	format!(
+		"COption{}",
+		value
+			.option_value_name()
+			.unwrap_or_default()
+			.to_case(Case::Pascal)
+	)
+}
+
+// Name of the `#[repr(C)]` buffer type generated for a `Vec`
+fn vec_mirror_name(element: &TypeRef) -> String {
+	format!(
+		"CVec{}",
+		element
+			.vec_element_name()
+			.unwrap_or_default()
+			.to_case(Case::Pascal)
+	)
+}
+
+// Generate the Rust extern "C" struct handling functions (constructor, destructor, etc.)
+fn generate_rust_struct_wrapper(api: &Api, struct_item: &Struct) -> String {
+	let struct_name_c = api.c_type_name(&struct_item.name);
+	let struct_path = struct_item.rust_path();
+	let mut rust_struct_wrapper = String::new();
+
+	rust_struct_wrapper.push_str(&generate_rust_constructor(api, struct_item));
+	rust_struct_wrapper
+		.push_str(&generate_rust_standard_trait_functions(api, struct_item));
+
+	// `#[repr(C)]` structs are returned by value and need no destructor
+	if struct_item.by_value {
+		return rust_struct_wrapper;
+	}
+
+	rust_struct_wrapper.push_str(&format!(
+		r#"
+#[no_mangle]
+pub extern "C" fn {struct_name_c}_free(obj: *mut {struct_path}) {{
+{body}}}
+"#,
+		body = catch_panics(
+			"    if !obj.is_null() {\n        unsafe {\n            let _ = Box::from_raw(obj);\n        }\n    }\n",
+			"()",
+		),
+	));
+
+	for (index, field) in api.accessor_fields(struct_item) {
+		rust_struct_wrapper.push_str(&generate_rust_accessors(
+			api,
+			struct_item,
+			index,
+			field,
+		));
+	}
+
+	rust_struct_wrapper
+}
+
+// Generate `<struct>_new`, unless a user-defined `new` takes its place or C
+// can't build the struct
+fn generate_rust_constructor(api: &Api, struct_item: &Struct) -> String {
+	if api.has_method(&struct_item.name, "new") {
+		return String::new();
+	}
+	let struct_path = struct_item.rust_path();
+	let output = TypeRef::Named(struct_item.name.clone());
+	let (inputs, call_path, construct) = match struct_item.constructor {
+		Constructor::Default => {
+			(Vec::new(), format!("{struct_path}::default"), String::new())
+		}
+		// The fields are converted like the parameters of a function
+		// building the struct
+		Constructor::Fields => {
+			let inputs = struct_item.constructor_params();
+			let params = inputs
+				.iter()
+				.map(|param| {
+					format!(
+						"{}: {}",
+						identifier(&param.name),
+						rust_type_name(api, &param.ty)
+					)
+				})
+				.collect::<Vec<_>>();
+			let fields = struct_item
+				.fields
+				.iter()
+				.zip(&inputs)
+				.map(|(field, param)| match &field.name {
+					Some(name) if *name != identifier(&param.name) => {
+						format!("{}: {}", name, identifier(&param.name))
+					}
+					_ => identifier(&param.name),
+				})
+				.collect::<Vec<_>>();
+			let literal =
+				if struct_item.fields.first().is_some_and(|f| f.name.is_none())
+				{
+					format!("{struct_path}({})", fields.join(", "))
+				} else {
+					format!("{struct_path} {{ {} }}", fields.join(", "))
+				};
+			let construct = format!(
+				"    fn construct({}) -> {struct_path} {{\n        {literal}\n    }}\n\n",
+				params.join(", ")
+			);
+			(inputs, "construct".to_string(), construct)
+		}
+		Constructor::None => return String::new(),
+	};
+
+	format!(
+		r#"
+#[no_mangle]
+pub extern "C" fn {struct_name_c}_new({params}){ret_type} {{
+{construct}{body}}}
+"#,
+		struct_name_c = api.c_type_name(&struct_item.name),
+		params = extern_c_params(api, &inputs, &output, api.utf8),
+		ret_type = extern_c_return(api, &output),
+		body = catch_panics(
+			&generate_rust_call(
+				api, &call_path, None, &inputs, &output, true, api.utf8,
+			),
+			"unsafe { core::mem::zeroed() }",
+		),
+	)
+}
+
+// Generate the functions calling the `Clone`, `PartialEq`, `Hash`, `Ord`,
+// `Display` and `Debug` implementations of a struct
+fn generate_rust_standard_trait_functions(
+	api: &Api,
+	struct_item: &Struct,
+) -> String {
+	let name = api.c_type_name(&struct_item.name);
+	let struct_path = struct_item.rust_path();
+	let on_null = "return unsafe { core::mem::zeroed() };";
+	let mut functions = String::new();
+
+	for (trait_name, suffix) in api.standard_trait_functions(&struct_item.name)
+	{
+		let (params, ret_type, body) = match trait_name {
+			"Clone" => {
+				let output = TypeRef::Named(struct_item.name.clone());
+				(
+					vec!["obj"],
+					extern_c_return(api, &output),
+					format!(
+						"    {}\n",
+						result_to_c(
+							api,
+							&output,
+							"Clone::clone(obj)",
+							&error_return(&output, "Error")
+						)
+					),
+				)
+			}
+			"PartialEq" => (
+				vec!["a", "b"],
+				" -> bool".to_string(),
+				"    PartialEq::eq(a, b)\n".to_string(),
+			),
+			"Hash" => (
+				vec!["obj"],
+				" -> u64".to_string(),
+				"    let mut hasher = std::collections::hash_map::DefaultHasher::new();\n    core::hash::Hash::hash(obj, &mut hasher);\n    core::hash::Hasher::finish(&hasher)\n".to_string(),
+			),
+			"Display" | "Debug" => (
+				vec!["obj"],
+				extern_c_return(api, &TypeRef::String),
+				format!(
+					"    {}\n",
+					result_to_c(
+						api,
+						&TypeRef::String,
+						if trait_name == "Display" {
+							"format!(\"{}\", obj)"
+						} else {
+							"format!(\"{:?}\", obj)"
+						},
+						&error_return(&TypeRef::String, "Error")
+					)
+				),
+			),
+			_ => (
+				vec!["a", "b"],
+				" -> i32".to_string(),
+				"    Ord::cmp(a, b) as i32\n".to_string(),
+			),
+		};
+
+		// Arguments are borrowed, so C keeps ownership
+		let checks = params
+			.iter()
+			.map(|param| {
+				format!(
+					"{}    let {param} = unsafe {{ &*{param} }};\n",
+					null_check(param, param, on_null)
+				)
+			})
+			.collect::<String>();
+		functions.push_str(&format!(
+			r#"
+#[no_mangle]
+pub extern "C" fn {name}_{suffix}({params}){ret_type} {{
+{body}}}
+"#,
+			params = params
+				.iter()
+				.map(|param| format!("{param}: *const {struct_path}"))
+				.collect::<Vec<_>>()
+				.join(", "),
+			body = catch_panics(
+				&format!("{checks}{body}"),
+				"unsafe { core::mem::zeroed() }"
+			),
+		));
+	}
+
+	functions
+}
+
+// Generate the functions calling the `Display` and `Debug` implementations
+// of an enum, read through a pointer and validated like any `&Enum` argument
+fn generate_rust_enum_formatting_functions(
+	api: &Api,
+	enum_item: &Enum,
+) -> String {
+	let obj = Param::new(
+		"obj".to_string(),
+		TypeRef::Reference {
+			mutable: false,
+			ty: Box::new(TypeRef::Named(enum_item.name.clone())),
+		},
+	);
+	let output = TypeRef::String;
+	let mut functions = String::new();
+
+	for (trait_name, suffix) in api.standard_trait_functions(&enum_item.name) {
+		functions.push_str(&format!(
+			r#"
+#[no_mangle]
+pub extern "C" fn {name}_{suffix}({params}){ret_type} {{
+    fn format_value(obj: &{enum_path}) -> String {{
+        format!("{spec}", obj)
+    }}
+
+{body}}}
+"#,
+			name = api.c_type_name(&enum_item.name),
+			params = extern_c_params(
+				api,
+				std::slice::from_ref(&obj),
+				&output,
+				api.utf8
+			),
+			ret_type = extern_c_return(api, &output),
+			enum_path = enum_item.rust_path(),
+			spec = if trait_name == "Display" {
+				"{}"
+			} else {
+				"{:?}"
+			},
+			body = catch_panics(
+				&generate_rust_call(
+					api,
+					"format_value",
+					None,
+					std::slice::from_ref(&obj),
+					&output,
+					true,
+					api.utf8,
+				),
+				"unsafe { core::mem::zeroed() }",
+			),
+		));
+	}
+
+	functions
+}
+
+// Generate the getter and setter for a public field, unless the struct has
+// methods with the same names
+fn generate_rust_accessors(
+	api: &Api,
+	struct_item: &Struct,
+	index: usize,
+	field: &Field,
+) -> String {
+	let struct_name_c = api.c_type_name(&struct_item.name);
+	let struct_path = struct_item.rust_path();
+	let suffix = field.accessor_suffix(index);
+	let field_name = field.name.clone().unwrap_or_else(|| index.to_string());
+	let mut accessors = String::new();
+
+	if !api.has_method(&struct_item.name, &format!("get_{}", suffix)) {
+		let on_error = error_return(&field.ty, "Error");
+		let (ret_type, value) = match &field.ty {
+			// Struct fields are lent out rather than copied
+			TypeRef::Named(name) if api.find_struct(name).is_some() => (
+				format!("*const {}", named_type_path(api, name)),
+				format!("&obj.{field_name} as *const _"),
+			),
+			TypeRef::Named(name) => (
+				"i32".to_string(),
+				format!("{}(&obj.{field_name})", enum_to_c_fn(name)),
+			),
+			TypeRef::String => (
+				return_type_to_rust_extern_c(api, &field.ty),
+				result_to_c(
+					api,
+					&field.ty,
+					&format!("obj.{field_name}.clone()"),
+					&on_error,
+				),
+			),
+			TypeRef::Char => (
+				return_type_to_rust_extern_c(api, &field.ty),
+				result_to_c(
+					api,
+					&field.ty,
+					&format!("obj.{field_name}"),
+					&on_error,
+				),
+			),
+			ty => (
+				return_type_to_rust_extern_c(api, ty),
+				format!("obj.{field_name}"),
+			),
+		};
+		accessors.push_str(&format!(
+			r#"
+#[no_mangle]
+pub extern "C" fn {struct_name_c}_get_{suffix}(obj: *const {struct_path}) -> {ret_type} {{
+{body}}}
+"#,
+			body = catch_panics(
+				&format!(
+					"{}    let obj = unsafe {{ &*obj }};\n    {value}\n",
+					null_check(
+						"obj",
+						"obj",
+						&error_return(&field.ty, "NullPointer")
+					),
+				),
+				"unsafe { core::mem::zeroed() }",
+			),
+		));
+	}
+
+	// The setter converts its argument like any function parameter
+	if !api.has_method(&struct_item.name, &format!("set_{}", suffix)) {
+		let value = Param::new("value".to_string(), field.ty.clone());
+		accessors.push_str(&format!(
+			r#"
+#[no_mangle]
+pub extern "C" fn {struct_name_c}_set_{suffix}(obj: *mut {struct_path}, {params}) {{
+    fn set_field(obj: &mut {struct_path}, value: {field_type}) {{
+        obj.{field_name} = value;
+    }}
+
+{body}}}
+"#,
+			params = extern_c_param(api, &value, api.utf8).join(", "),
+			field_type = rust_type_name(api, &field.ty),
+			body = catch_panics(
+				&format!(
+					"{}    let obj = unsafe {{ &mut *obj }};\n{}",
+					null_check("obj", "obj", "return;"),
+					generate_rust_call(
+						api,
+						"set_field",
+						Some("obj"),
+						&[value],
+						&TypeRef::Unit,
+						true,
+						api.utf8,
+					),
+				),
+				"()",
+			),
+		));
+	}
+
+	accessors
+}
+
+// Map model types to the types used in the extern "C" wrapper signatures
+pub fn rust_type_to_rust_extern_c(api: &Api, ty: &TypeRef) -> String {
+	match ty {
+		TypeRef::Unit => "()".to_string(),
+		TypeRef::Primitive(primitive) => primitive.rust_name().to_string(),
+		// Not every `u32` is a valid `char`
+		TypeRef::Char => "u32".to_string(),
+		TypeRef::String => "*mut core::ffi::c_char".to_string(),
+		TypeRef::Str | TypeRef::StaticStr => {
+			"*const core::ffi::c_char".to_string()
+		}
+		TypeRef::Vec(inner) if inner.vec_element_name().is_some() => {
+			vec_mirror_name(inner)
+		}
+		TypeRef::Vec(_) => "*mut core::ffi::c_void".to_string(),
+		TypeRef::Option(inner) if inner.option_value_name().is_some() => {
+			option_mirror_name(inner)
+		}
+		// Other options are nullable pointers
+		TypeRef::Option(inner) => rust_type_to_rust_extern_c(api, inner),
+		TypeRef::Result { .. } => "Status".to_string(),
+		// Fieldless enums cross the boundary as C ints
+		TypeRef::Named(name) if api.is_c_enum(name) => "i32".to_string(),
+		TypeRef::Named(name) if api.is_tagged_union(name) => {
+			c_mirror_name(name)
+		}
+		TypeRef::Named(name) if api.is_by_value_struct(name) => {
+			named_type_path(api, name)
+		}
+		TypeRef::Named(name) => format!("*mut {}", named_type_path(api, name)),
+		TypeRef::Boxed(inner) if is_struct_type(api, inner) => {
+			rust_type_to_rust_extern_c(api, inner)
+		}
+		TypeRef::Boxed(inner) if is_trait_object(api, inner) => {
+			let TypeRef::DynTrait(trait_name) = &**inner else {
+				unreachable!()
+			};
+			format!("{}Dyn", c_mirror_name(trait_name))
+		}
+		TypeRef::Boxed(_) | TypeRef::DynTrait(_) | TypeRef::ImplTrait(_) => {
+			"*mut core::ffi::c_void".to_string()
+		}
+		TypeRef::Slice { mutable, ty } => {
+			let mutability = if *mutable { "mut" } else { "const" };
+			format!("*{} {}", mutability, rust_type_to_rust_extern_c(api, ty))
+		}
+		TypeRef::Reference { mutable, ty } => {
+			let mutability = if *mutable { "mut" } else { "const" };
+			match &**ty {
+				TypeRef::Named(name) if api.is_c_enum(name) => {
+					format!("*{} i32", mutability)
+				}
+				TypeRef::Named(name) if api.is_tagged_union(name) => {
+					format!("*{} {}", mutability, c_mirror_name(name))
+				}
+				TypeRef::Named(name) => {
+					format!("*{} {}", mutability, named_type_path(api, name))
+				}
+				TypeRef::Primitive(primitive) => {
+					format!("*{} {}", mutability, primitive.rust_name())
+				}
+				_ => format!("*{} core::ffi::c_void", mutability),
+			}
+		}
+		TypeRef::Iterator(item) => {
+			format!("*mut {}", iterator_mirror_name(api, item))
+		}
+		TypeRef::Unsupported(_) => "*mut core::ffi::c_void".to_string(),
+	}
+}
+
+// Map parameter types to extern "C" types; trait objects passed to Rust are
+// implemented in C through callback tables
+fn param_type_to_rust_extern_c(api: &Api, ty: &TypeRef) -> String {
+	match (ty, ty.trait_object_name()) {
+		(TypeRef::Reference { .. }, Some(trait_name)) => {
+			format!("*const {}Callbacks", c_mirror_name(trait_name))
+		}
+		(_, Some(trait_name)) => {
+			format!("{}Callbacks", c_mirror_name(trait_name))
+		}
+		_ => rust_type_to_rust_extern_c(api, ty),
+	}
+}
+
+// Map types exchanged with C callbacks, which only borrow strings
+fn callback_type_to_rust_extern_c(api: &Api, ty: &TypeRef) -> String {
+	match ty {
+		TypeRef::String => "*const core::ffi::c_char".to_string(),
+		ty => rust_type_to_rust_extern_c(api, ty),
+	}
+}
+
+// Spell out a model type as Rust source, for trait method signatures
+fn rust_type_name(api: &Api, ty: &TypeRef) -> String {
+	match ty {
+		TypeRef::Unit => "()".to_string(),
+		TypeRef::Primitive(primitive) => primitive.rust_name().to_string(),
+		TypeRef::Char => "char".to_string(),
+		TypeRef::String => "String".to_string(),
+		TypeRef::Str => "&str".to_string(),
+		TypeRef::StaticStr => "&'static str".to_string(),
+		TypeRef::Vec(inner) => format!("Vec<{}>", rust_type_name(api, inner)),
+		TypeRef::Option(inner) => {
+			format!("Option<{}>", rust_type_name(api, inner))
+		}
+		TypeRef::Named(name) => named_type_path(api, name),
+		TypeRef::Boxed(inner) => format!("Box<{}>", rust_type_name(api, inner)),
+		TypeRef::DynTrait(name) => format!("dyn {}", trait_path(api, name)),
+		TypeRef::ImplTrait(name) => format!("impl {}", trait_path(api, name)),
+		TypeRef::Reference { mutable, ty } => format!(
+			"&{}{}",
+			if *mutable { "mut " } else { "" },
+			rust_type_name(api, ty)
+		),
+		TypeRef::Slice { mutable, ty } => format!(
+			"&{}[{}]",
+			if *mutable { "mut " } else { "" },
+			rust_type_name(api, ty)
+		),
+		TypeRef::Result { ok, err } => format!(
+			"Result<{}, {}>",
+			rust_type_name(api, ok),
+			rust_type_name(api, err)
+		),
+		TypeRef::Iterator(item) => {
+			format!("impl Iterator<Item = {}>", rust_type_name(api, item))
+		}
+		TypeRef::Unsupported(source) => source.clone(),
+	}
+}
+
+// Resolve a crate trait name to its fully qualified path
+fn trait_path(api: &Api, name: &str) -> String {
+	api.find_trait(name)
+		.map(Trait::rust_path)
+		.unwrap_or_else(|| name.to_string())
+}
+
+// Resolve a crate type name to its fully qualified path
+fn named_type_path(api: &Api, name: &str) -> String {
+	api.find_struct(name)
+		.map(Struct::rust_path)
+		.or_else(|| api.find_enum(name).map(|e| e.rust_path()))
+		.unwrap_or_else(|| name.to_string())
+}
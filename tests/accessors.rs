@@ -0,0 +1,42 @@
+mod common;
+
+use common::{generate, squash};
+
+const HOLDER: &str = "
+	pub struct Holder { pub later: Later, pub name: String, pub count: u32, id: u32 }
+	pub struct Later { id: u32 }
+";
+
+#[test]
+fn public_fields_get_accessors() {
+	let bindings = generate(HOLDER);
+
+	for prototype in [
+		"// Points into `obj`, valid until `obj` is freed or the field is set\nconst later* holder_get_later(const holder* obj);",
+		"// Takes ownership of `value`\nvoid holder_set_later(holder* obj, later* value);",
+		"// The returned string belongs to the caller, release it with test_crate_string_free\nchar* holder_get_name(const holder* obj);",
+		"// Copies `value`, which stays owned by the caller\nvoid holder_set_name(holder* obj, char* value);",
+		"uint32_t holder_get_count(const holder* obj);",
+		"void holder_set_count(holder* obj, uint32_t value);",
+	] {
+		assert!(bindings.header.contains(prototype), "{}", prototype);
+	}
+	assert!(!bindings.header.contains("holder_get_id"));
+	assert!(squash(&bindings.wrapper).contains(
+		"fn set_field(obj: &mut test_crate::Holder, value: String) { obj.name = value; }"
+	));
+}
+
+#[test]
+fn opaque_handles_are_declared_before_any_prototype() {
+	let bindings = generate(HOLDER);
+	let header = &bindings.header;
+	let first_prototype = header.find("holder_get_later").unwrap();
+
+	assert!(
+		header.find("typedef struct holder holder;").unwrap() < first_prototype
+	);
+	assert!(
+		header.find("typedef struct later later;").unwrap() < first_prototype
+	);
+}
@@ -0,0 +1,53 @@
+mod common;
+
+use common::{generate, squash};
+
+const LOGGER: &str = "
+	pub trait Logger {
+		fn log(&self, message: &str);
+		fn level(&self) -> u32;
+	}
+	pub fn run(logger: Box<dyn Logger>) {}
+	pub fn peek(logger: &dyn Logger) {}
+";
+
+#[test]
+fn traits_taken_as_arguments_are_implemented_by_callbacks() {
+	let bindings = generate(LOGGER);
+	let header = squash(&bindings.header);
+
+	assert!(header.contains(
+		"typedef struct logger_callbacks { void* user_data; void (*log)(void* user_data, const char* message); uint32_t (*level)(void* user_data); // Called once Rust drops an implementation it owns, may be NULL void (*free)(void* user_data); } logger_callbacks;"
+	));
+	assert!(header.contains("void run(logger_callbacks logger);"));
+	assert!(header.contains("void peek(const logger_callbacks* logger);"));
+}
+
+#[test]
+fn null_callbacks_fail_the_call() {
+	let bindings = generate(LOGGER);
+	let wrapper = squash(&bindings.wrapper);
+
+	assert!(wrapper.contains(
+		"if callbacks.log.is_none() { return Err(\"the `log` callback is NULL\".to_string()); }"
+	));
+	assert!(wrapper.contains(
+		"if callbacks.level.is_none() { return Err(\"the `level` callback is NULL\".to_string()); }"
+	));
+	assert!(wrapper.contains(
+		"match LoggerAdapter::owned(logger) { Ok(value) => value, Err(error) => { set_last_error(error); return; } }"
+	));
+	assert!(wrapper.contains(
+		"match LoggerAdapter::borrowed(unsafe { *logger }) { Ok(value) => value, Err(error) => { set_last_error(error); return; } }"
+	));
+	assert!(!wrapper.contains(".unwrap()"));
+}
+
+#[test]
+fn strings_passed_to_callbacks_escape_nul_bytes() {
+	let bindings = generate(LOGGER);
+
+	assert!(bindings.wrapper.contains(
+		"std::ffi::CString::new(message.replace('\\0', \"\\\\0\")).unwrap_or_default()"
+	));
+}
@@ -0,0 +1,62 @@
+// Shared helpers for the tests running the whole pipeline on a source file
+
+// Each test file only uses some of them
+#![allow(dead_code)]
+
+use std::path::Path;
+
+use cbt::{build_api, c_header, model::Api, rust_wrapper};
+
+// What cbt produces for a crate: the C header, the Rust wrapper crate and
+// the warnings it printed
+pub struct Bindings {
+	pub header: String,
+	pub wrapper: String,
+	pub diagnostics: Vec<String>,
+}
+
+impl Bindings {
+	// Whether some warning mentions every one of `parts`
+	pub fn warns(&self, parts: &[&str]) -> bool {
+		self.diagnostics.iter().any(|diagnostic| {
+			parts.iter().all(|part| diagnostic.contains(part))
+		})
+	}
+}
+
+// Generate the bindings of a crate named `test_crate` whose `lib.rs` is
+// `source`, instantiating the generic items listed
+pub fn generate_with(source: &str, instantiations: &[&str]) -> Bindings {
+	let api = api(source, instantiations);
+	Bindings {
+		header: c_header::generate(&api),
+		wrapper: rust_wrapper::generate(&api),
+		diagnostics: api.diagnostics,
+	}
+}
+
+pub fn generate(source: &str) -> Bindings {
+	generate_with(source, &[])
+}
+
+pub fn api(source: &str, instantiations: &[&str]) -> Api {
+	let file = syn::parse_file(source).unwrap();
+	let instantiations = instantiations
+		.iter()
+		.map(|instance| instance.to_string())
+		.collect::<Vec<_>>();
+	build_api(
+		&file.items,
+		Path::new("."),
+		false,
+		"test_crate",
+		"test_crate",
+		&instantiations,
+	)
+}
+
+// Collapse runs of whitespace so generated code can be matched regardless of
+// how it is indented
+pub fn squash(code: &str) -> String {
+	code.split_whitespace().collect::<Vec<_>>().join(" ")
+}
@@ -0,0 +1,57 @@
+mod common;
+
+use common::generate;
+
+#[test]
+fn structs_with_public_fields_are_built_field_by_field() {
+	let bindings = generate(
+		"
+		pub struct Config { pub name: String, pub size: u32 }
+		pub struct Holder { pub later: Later }
+		pub struct Later;
+		",
+	);
+
+	assert!(bindings
+		.header
+		.contains("config* config_new(char* name, uint32_t size);"));
+	assert!(bindings.header.contains(
+		"// Takes ownership of `later`\nholder* holder_new(later* later);"
+	));
+	assert!(
+		bindings.header.find("typedef struct later later;").unwrap()
+			< bindings.header.find("holder_new").unwrap()
+	);
+}
+
+#[test]
+fn constructors_without_fields_take_void() {
+	let bindings = generate(
+		"
+		pub struct Empty;
+		#[derive(Default)]
+		pub struct Counter { count: u32 }
+		",
+	);
+
+	assert!(bindings.header.contains("empty* empty_new(void);"));
+	assert!(bindings.header.contains("counter* counter_new(void);"));
+}
+
+#[test]
+fn structs_with_their_own_new_or_private_fields_get_no_constructor() {
+	let bindings = generate(
+		"
+		pub struct Private { id: u32 }
+		pub struct Custom { id: u32 }
+		impl Custom { pub fn new(id: u32) -> Self { Custom { id } } }
+		",
+	);
+
+	assert!(
+		bindings.warns(&["constructor for `Private`", "field `id` is private"])
+	);
+	assert!(!bindings.header.contains("private_new"));
+	assert!(bindings.header.contains("custom* custom_new(uint32_t id);"));
+	assert_eq!(bindings.header.matches("custom_new(").count(), 1);
+}
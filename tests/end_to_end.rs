@@ -0,0 +1,166 @@
+mod common;
+
+use std::{fs, path::Path, process::Command};
+
+use common::generate_with;
+
+// A crate touching every kind of binding, so the generated code has to hold
+// together as a whole
+const SOURCE: &str = r#"
+	use std::fmt;
+
+	#[repr(C)]
+	#[derive(Clone, Copy, Default, Debug, PartialEq)]
+	pub struct Point { pub x: f64, pub y: f64 }
+
+	#[derive(Clone, Debug, Default, PartialEq, Hash)]
+	pub struct Holder { pub later: Later, pub name: String, pub count: u32 }
+
+	#[derive(Clone, Debug, Default, PartialEq, Hash)]
+	pub struct Later { pub id: u32 }
+
+	#[derive(Debug)]
+	pub enum Level { Low, High = 5 }
+
+	pub enum Shape { Circle(f64), Named { name: String, sides: u8 }, Empty }
+
+	#[derive(Debug)]
+	pub struct ParseError;
+	impl fmt::Display for ParseError {
+		fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+			write!(f, "not a number")
+		}
+	}
+
+	pub trait Area {
+		fn area(&self) -> f64;
+		fn label(&self, prefix: &str) -> String;
+	}
+	impl Area for Point {
+		fn area(&self) -> f64 { self.x * self.y }
+		fn label(&self, prefix: &str) -> String { prefix.to_string() }
+	}
+	impl Area for Holder {
+		fn area(&self) -> f64 { 0.0 }
+		fn label(&self, prefix: &str) -> String { prefix.to_string() }
+	}
+
+	pub struct Countdown { left: u32 }
+	impl Countdown {
+		pub fn new(left: u32) -> Self { Countdown { left } }
+	}
+	impl Iterator for Countdown {
+		type Item = u32;
+		fn next(&mut self) -> Option<u32> {
+			self.left = self.left.checked_sub(1)?;
+			Some(self.left)
+		}
+	}
+
+	/// cbt: instantiate = f32
+	pub struct Matrix<T> { pub value: T }
+
+	pub fn parse(text: &str) -> Result<u32, ParseError> {
+		text.parse().map_err(|_| ParseError)
+	}
+	pub fn shift(p: Point, by: &Point) -> Point {
+		Point { x: p.x + by.x, y: p.y + by.y }
+	}
+	pub fn raise(level: Level) -> Level { level }
+	pub fn describe(shape: &Shape) -> f64 { 0.0 }
+	pub fn sum<T: Copy + Default>(values: &[T]) -> T { T::default() }
+	pub fn evens(limit: u32) -> Vec<u32> { (0..limit).step_by(2).collect() }
+	pub fn find(n: u32) -> Option<u32> { Some(n) }
+	pub fn first(n: u32) -> Option<Later> { None }
+	pub fn total(area: &dyn Area) -> f64 { area.area() }
+	pub fn numbers() -> Box<dyn Iterator<Item = u32>> { Box::new(0..3) }
+	pub fn version() -> &'static str { "1" }
+"#;
+
+fn has_tool(tool: &str) -> bool {
+	Command::new(tool).arg("--version").output().is_ok()
+}
+
+#[test]
+fn generated_header_compiles_as_strict_c99() {
+	if !has_tool("gcc") {
+		eprintln!("gcc isn't installed, not compiling the header");
+		return;
+	}
+	let bindings = generate_with(SOURCE, &["sum<u64>"]);
+	assert!(
+		bindings.diagnostics.is_empty(),
+		"{:?}",
+		bindings.diagnostics
+	);
+	let dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("end_to_end_c");
+	fs::create_dir_all(&dir).unwrap();
+	fs::write(dir.join("bindings.h"), &bindings.header).unwrap();
+	fs::write(dir.join("main.c"), "#include \"bindings.h\"\n").unwrap();
+
+	let output = Command::new("gcc")
+		.args([
+			"-std=c99",
+			"-Wall",
+			"-Wextra",
+			"-Wstrict-prototypes",
+			"-Werror",
+			"-fsyntax-only",
+		])
+		.arg(dir.join("main.c"))
+		.output()
+		.unwrap();
+
+	assert!(
+		output.status.success(),
+		"{}",
+		String::from_utf8_lossy(&output.stderr)
+	);
+}
+
+#[test]
+fn generated_wrapper_builds_against_the_crate() {
+	if !has_tool("cargo") {
+		eprintln!("cargo isn't installed, not building the wrapper");
+		return;
+	}
+	let bindings = generate_with(SOURCE, &["sum<u64>"]);
+	let dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("end_to_end_rust");
+	fs::create_dir_all(dir.join("src")).unwrap();
+	fs::create_dir_all(dir.join("c_api/src")).unwrap();
+	fs::write(
+		dir.join("Cargo.toml"),
+		"[package]\nname = \"test_crate\"\nversion = \"0.1.0\"\nedition = \"2021\"\n\n[workspace]\nmembers = [\"c_api\"]\n",
+	)
+	.unwrap();
+	fs::write(
+		dir.join("src/lib.rs"),
+		format!("#![allow(unused)]\n{}", SOURCE),
+	)
+	.unwrap();
+	fs::write(
+		dir.join("c_api/Cargo.toml"),
+		"[package]\nname = \"test_crate_c_api\"\nversion = \"0.1.0\"\nedition = \"2021\"\n\n[dependencies]\ntest_crate = { path = \"../\" }\n",
+	)
+	.unwrap();
+	fs::write(dir.join("c_api/src/lib.rs"), &bindings.wrapper).unwrap();
+
+	let output = Command::new("cargo")
+		.args([
+			"build",
+			"--offline",
+			"--quiet",
+			"--package",
+			"test_crate_c_api",
+		])
+		.current_dir(&dir)
+		.env("RUSTFLAGS", "-D warnings")
+		.output()
+		.unwrap();
+
+	assert!(
+		output.status.success(),
+		"{}",
+		String::from_utf8_lossy(&output.stderr)
+	);
+}
@@ -0,0 +1,84 @@
+mod common;
+
+use common::{generate, squash};
+
+#[test]
+fn fieldless_enums_keep_their_discriminants() {
+	let bindings = generate(
+		"
+		pub enum Level { Low, High = 5, Next, Negative = -3 }
+		pub fn raise(level: Level) -> Level { level }
+		",
+	);
+
+	assert!(bindings.header.contains(
+		"typedef enum level {\n    LEVEL_LOW = 0,\n    LEVEL_HIGH = 5,\n    LEVEL_NEXT = 6,\n    LEVEL_NEGATIVE = -3,\n} level;"
+	));
+	assert!(bindings.header.contains("level raise(level level);"));
+}
+
+#[test]
+fn enums_from_c_are_validated() {
+	let bindings = generate(
+		"
+		pub enum Level { Low, High }
+		pub fn raise(level: Level) {}
+		pub fn peek(level: &Level) {}
+		",
+	);
+	let wrapper = squash(&bindings.wrapper);
+
+	assert!(wrapper.contains(
+		"fn level_from_c(value: i32) -> Option<test_crate::Level> { match value { 0 => Some(test_crate::Level::Low), 1 => Some(test_crate::Level::High), _ => None, } }"
+	));
+	assert!(wrapper.contains(
+		"level_from_c(level).ok_or(\"`level` is not a valid `Level`\")"
+	));
+	assert!(wrapper.contains(
+		"level_from_c(unsafe { *level }).ok_or(\"`level` does not point to a valid `Level`\")"
+	));
+}
+
+const SHAPE: &str = "
+	pub enum Shape { Circle { radius: f64 }, Label(String), Empty }
+	pub fn area(shape: &Shape) -> f64 { 0.0 }
+	/// cbt: utf8 = lossy
+	pub fn describe(shape: Shape) {}
+";
+
+#[test]
+fn data_carrying_enums_become_tagged_unions() {
+	let bindings = generate(SHAPE);
+
+	assert!(bindings.header.contains(
+		"typedef enum shape_tag {\n    SHAPE_CIRCLE = 0,\n    SHAPE_LABEL = 1,\n    SHAPE_EMPTY = 2,\n} shape_tag;"
+	));
+	assert!(bindings.header.contains(
+		"typedef struct shape {\n    shape_tag tag;\n    union {\n        shape_circle circle;\n        shape_label label;\n    } data;\n} shape;"
+	));
+	assert!(bindings.header.contains(
+		"typedef struct shape_label {\n    char* _0;\n} shape_label;"
+	));
+	assert!(bindings.header.contains("void shape_free(shape* value);"));
+	assert!(bindings.header.contains("double area(const shape* shape);"));
+}
+
+#[test]
+fn tagged_union_strings_follow_the_utf8_policy() {
+	let bindings = generate(SHAPE);
+	let wrapper = squash(&bindings.wrapper);
+
+	assert!(wrapper.contains(
+		"if lossy { String::from_utf8_lossy(bytes).into_owned() } else { core::str::from_utf8(bytes).ok()?.to_string() }"
+	));
+	assert!(wrapper.contains("shape_from_c(&unsafe { *shape }, false)"));
+	assert!(wrapper.contains("shape_from_c(&shape, true)"));
+}
+
+#[test]
+fn payloads_without_a_c_layout_skip_the_enum() {
+	let bindings = generate("pub enum Bytes { Holds(Vec<u8>) }");
+
+	assert!(bindings.warns(&["skipping enum `Bytes`", "tagged union"]));
+	assert!(!bindings.header.contains("} bytes;"));
+}
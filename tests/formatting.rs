@@ -0,0 +1,63 @@
+mod common;
+
+use common::{generate, squash};
+
+#[test]
+fn enums_are_formatted_through_a_pointer_to_their_c_value() {
+	let bindings = generate(
+		"
+		#[derive(Debug)]
+		pub enum Level { Low, High }
+		impl std::fmt::Display for Level {
+			fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+				Ok(())
+			}
+		}
+		",
+	);
+	let wrapper = squash(&bindings.wrapper);
+
+	assert!(bindings.header.contains(
+		"// The returned string belongs to the caller, release it with test_crate_string_free\nchar* level_to_string(const level* obj);"
+	));
+	assert!(bindings
+		.header
+		.contains("char* level_debug_string(const level* obj);"));
+	assert!(wrapper.contains(
+		"fn level_to_string(obj: *const i32) -> *mut core::ffi::c_char { fn format_value(obj: &test_crate::Level) -> String { format!(\"{}\", obj) }"
+	));
+	assert!(wrapper.contains(
+		"fn format_value(obj: &test_crate::Level) -> String { format!(\"{:?}\", obj) }"
+	));
+	assert!(wrapper.contains("level_from_c(unsafe { *obj })"));
+}
+
+#[test]
+fn structs_are_formatted_through_their_handle() {
+	let bindings = generate(
+		"
+		#[derive(Debug)]
+		pub struct Item { id: u32 }
+		",
+	);
+
+	assert!(bindings
+		.header
+		.contains("char* item_debug_string(const item* obj);"));
+	assert!(!bindings.header.contains("item_to_string"));
+}
+
+#[test]
+fn enums_only_get_formatting_functions() {
+	let bindings = generate(
+		"
+		#[derive(Clone, Debug, PartialEq, Hash)]
+		pub enum Level { Low, High }
+		",
+	);
+
+	assert!(bindings.header.contains("level_debug_string("));
+	for suffix in ["_clone(", "_equals(", "_hash("] {
+		assert!(!bindings.header.contains(&format!("level{}", suffix)));
+	}
+}
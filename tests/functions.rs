@@ -0,0 +1,81 @@
+mod common;
+
+use cbt::model::Ownership;
+use common::{api, generate};
+
+#[test]
+fn parameters_are_named_after_their_binding() {
+	let bindings = generate(
+		"
+		pub fn bump(mut x: i32) -> i32 { x += 1; x }
+		pub fn ignore(_: i32, _: u8) {}
+		pub fn r#type(r#struct: u32) -> u32 { r#struct }
+		",
+	);
+
+	assert!(bindings.header.contains("int32_t bump(int32_t x);"));
+	assert!(bindings.wrapper.contains("test_crate::bump(x)"));
+	assert!(bindings
+		.header
+		.contains("void ignore(int32_t arg0, uint8_t arg1);"));
+	assert!(bindings.wrapper.contains("test_crate::ignore(arg0, arg1)"));
+	assert!(bindings
+		.header
+		.contains("uint32_t type_(uint32_t struct_);"));
+	assert!(bindings.wrapper.contains("test_crate::r#type(struct_)"));
+}
+
+#[test]
+fn function_names_avoid_keywords() {
+	let bindings = generate("pub fn int(x: u32) -> u32 { x }");
+
+	assert!(bindings.header.contains("uint32_t int_(uint32_t x);"));
+	assert!(bindings
+		.wrapper
+		.contains("pub extern \"C\" fn int_(x: u32)"));
+}
+
+#[test]
+fn parameterless_functions_are_prototypes() {
+	let bindings = generate("pub fn version() -> u32 { 1 }");
+
+	assert!(bindings.header.contains("uint32_t version(void);"));
+}
+
+#[test]
+fn parameters_without_a_c_type_skip_the_function() {
+	let bindings = generate("pub fn pair(p: (i32, i32)) -> i32 { p.0 }");
+
+	assert!(bindings.warns(&["`pair`", "can't be passed from C"]));
+	assert!(!bindings.header.contains("pair("));
+	assert!(!bindings.wrapper.contains("transmute"));
+}
+
+#[test]
+fn ownership_is_recorded_for_parameters_and_results() {
+	let api = api(
+		"
+		pub struct Item { pub id: u32 }
+		pub fn keep(item: Item, peek: &Item, edit: &mut Item) -> String {
+			String::new()
+		}
+		",
+		&[],
+	);
+
+	let keep = api.functions.iter().find(|f| f.name == "keep").unwrap();
+	let ownership = keep
+		.inputs
+		.iter()
+		.map(|param| param.ownership)
+		.collect::<Vec<_>>();
+	assert_eq!(
+		ownership,
+		[
+			Ownership::Owned,
+			Ownership::Borrowed,
+			Ownership::BorrowedMut
+		]
+	);
+	assert_eq!(keep.output_ownership, Ownership::Owned);
+}
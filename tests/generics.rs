@@ -0,0 +1,48 @@
+mod common;
+
+use common::{generate, generate_with, squash};
+
+#[test]
+fn generic_structs_are_exported_per_instantiation() {
+	let bindings = generate(
+		"
+		/// cbt: instantiate = f32
+		/// cbt: instantiate = f64
+		pub struct Matrix<T> { pub value: T }
+		impl<T> Matrix<T> { pub fn get(&self) -> T { todo!() } }
+		",
+	);
+
+	for prototype in [
+		"typedef struct matrix_f32 matrix_f32;",
+		"matrix_f32* matrix_f32_new(float value);",
+		"float matrix_f32_get(const matrix_f32* self);",
+		"typedef struct matrix_f64 matrix_f64;",
+		"double matrix_f64_get(const matrix_f64* self);",
+	] {
+		assert!(bindings.header.contains(prototype), "{}", prototype);
+	}
+	assert!(squash(&bindings.wrapper)
+		.contains("test_crate::Matrix::<f32>::get(self_)"));
+}
+
+#[test]
+fn generic_functions_are_instantiated_from_the_list() {
+	let bindings = generate_with(
+		"pub fn sum<T>(values: &[T]) -> T { todo!() }",
+		&["sum<u64>"],
+	);
+
+	assert!(bindings.header.contains(
+		"uint64_t sum_u64(const uint64_t* values, size_t values_len);"
+	));
+	assert!(bindings.wrapper.contains("test_crate::sum::<u64>(values)"));
+}
+
+#[test]
+fn generic_items_without_instantiations_are_skipped() {
+	let bindings = generate("pub fn unused<T>(value: T) {}");
+
+	assert!(bindings.warns(&["skipping function `unused`", "no instantiation"]));
+	assert!(!bindings.header.contains("unused"));
+}
@@ -0,0 +1,58 @@
+mod common;
+
+use common::{generate, squash};
+
+#[test]
+fn returned_iterators_become_handles() {
+	let bindings = generate(
+		"pub fn numbers() -> Box<dyn Iterator<Item = u32>> { Box::new(0..3) }",
+	);
+	let wrapper = squash(&bindings.wrapper);
+
+	for prototype in [
+		"typedef struct test_crate_iterator_u32 test_crate_iterator_u32;",
+		"bool test_crate_iterator_u32_next(test_crate_iterator_u32* iter, uint32_t* out);",
+		"void test_crate_iterator_u32_free(test_crate_iterator_u32* iter);",
+		"test_crate_iterator_u32* numbers(void);",
+	] {
+		assert!(bindings.header.contains(prototype), "{}", prototype);
+	}
+	assert!(wrapper.contains(
+		"match unsafe { &mut *iter }.0.next() { Some(value) => { unsafe { *out = value }; true } None => false, }"
+	));
+	assert!(wrapper
+		.contains("Box::into_raw(Box::new(CIteratorU32(Box::new(result))))"));
+}
+
+#[test]
+fn iterator_structs_get_next() {
+	let bindings = generate(
+		"
+		pub struct Countdown { left: u32 }
+		impl Iterator for Countdown {
+			type Item = u32;
+			fn next(&mut self) -> Option<u32> { None }
+		}
+		",
+	);
+
+	assert!(bindings
+		.header
+		.contains("bool countdown_next(countdown* iter, uint32_t* out);"));
+	assert!(squash(&bindings.wrapper)
+		.contains("match Iterator::next(unsafe { &mut *iter })"));
+}
+
+#[test]
+fn borrowing_iterators_are_skipped() {
+	let bindings = generate(
+		"
+		pub fn words(text: &str) -> impl Iterator<Item = String> + '_ {
+			text.split(' ').map(String::from)
+		}
+		",
+	);
+
+	assert!(bindings.warns(&["skipping function `words`"]));
+	assert!(!bindings.header.contains("words("));
+}
@@ -0,0 +1,66 @@
+mod common;
+
+use common::generate;
+
+const COUNTER: &str = "
+	pub struct Counter { count: u32 }
+	impl Counter {
+		pub fn new() -> Self { Counter { count: 0 } }
+		pub fn add(&mut self, n: u32) { self.count += n; }
+		pub fn get(&self) -> u32 { self.count }
+		pub fn into_count(self) -> u32 { self.count }
+		fn hidden(&self) {}
+	}
+";
+
+#[test]
+fn methods_take_their_receiver_first() {
+	let bindings = generate(COUNTER);
+
+	assert!(bindings.header.contains("counter* counter_new(void);"));
+	assert!(bindings
+		.header
+		.contains("void counter_add(counter* self, uint32_t n);"));
+	assert!(bindings
+		.header
+		.contains("uint32_t counter_get(const counter* self);"));
+	assert!(bindings.header.contains(
+		"// Takes ownership of `self`\nuint32_t counter_into_count(counter* self);"
+	));
+	assert!(bindings
+		.wrapper
+		.contains("test_crate::Counter::add(self_, n)"));
+	assert!(!bindings.header.contains("hidden"));
+}
+
+#[test]
+fn methods_named_like_generated_functions_are_skipped() {
+	let bindings = generate(
+		"
+		pub struct Counter { count: u32 }
+		impl Counter {
+			pub fn free(&self) {}
+			pub fn drawable_vtable(&self) {}
+		}
+		pub trait Drawable { fn draw(&self); }
+		impl Drawable for Counter { fn draw(&self) {} }
+		pub enum Shape { Circle(f64) }
+		impl Shape {
+			pub fn release(&self) {}
+		}
+		",
+	);
+
+	assert!(bindings.warns(&["`Counter::free`", "`counter_free`"]));
+	assert!(bindings.warns(&["`Counter::drawable_vtable`"]));
+	assert!(bindings.warns(&["`Shape::release`", "`shape_release`"]));
+	assert_eq!(bindings.header.matches("void counter_free(").count(), 1);
+	assert_eq!(
+		bindings
+			.wrapper
+			.matches("fn counter_drawable_vtable(")
+			.count(),
+		1
+	);
+	assert_eq!(bindings.wrapper.matches("fn shape_release(").count(), 1);
+}
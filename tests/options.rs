@@ -0,0 +1,55 @@
+mod common;
+
+use common::{generate, squash};
+
+#[test]
+fn optional_pointers_are_nullable() {
+	let bindings = generate(
+		"
+		pub struct Item { id: u32 }
+		pub fn first(n: u32) -> Option<Item> { None }
+		pub fn peek(item: Option<&Item>) {}
+		",
+	);
+	let wrapper = squash(&bindings.wrapper);
+
+	assert!(bindings.header.contains("item* first(uint32_t n);"));
+	assert!(bindings.header.contains("void peek(const item* item);"));
+	assert!(wrapper.contains(
+		"match result { Some(value) => Box::into_raw(Box::new(value)), None => core::ptr::null_mut() }"
+	));
+	assert!(wrapper.contains("let item = unsafe { item.as_ref() };"));
+	assert!(!wrapper.contains("`item` is NULL"));
+}
+
+#[test]
+fn optional_values_carry_a_presence_flag() {
+	let bindings = generate(
+		"
+		pub fn find(n: u32) -> Option<u32> { None }
+		pub fn maybe(n: Option<u32>) {}
+		",
+	);
+	let header = squash(&bindings.header);
+	let wrapper = squash(&bindings.wrapper);
+
+	assert!(header.contains(
+		"typedef struct test_crate_option_u32 { bool has_value; uint32_t value; } test_crate_option_u32;"
+	));
+	assert!(header.contains("test_crate_option_u32 find(uint32_t n);"));
+	assert!(header.contains("void maybe(test_crate_option_u32 n);"));
+	assert!(wrapper.contains(
+		"COptionU32 { has_value: value.is_some(), value: value.unwrap_or_default() }"
+	));
+	assert!(wrapper.contains("let n = n.has_value.then_some(n.value);"));
+}
+
+#[test]
+fn optional_strings_are_nullable() {
+	let bindings = generate("pub fn name(n: u32) -> Option<String> { None }");
+
+	assert!(bindings.header.contains("char* name(uint32_t n);"));
+	assert!(
+		squash(&bindings.wrapper).contains("None => core::ptr::null_mut() }")
+	);
+}
@@ -0,0 +1,60 @@
+mod common;
+
+use common::{generate, squash};
+
+#[test]
+fn results_return_a_status_and_write_their_value_to_out() {
+	let bindings = generate(
+		"
+		pub struct ParseError;
+		impl std::fmt::Display for ParseError {
+			fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+				write!(f, \"bad number\")
+			}
+		}
+		pub fn parse(text: &str) -> Result<u32, ParseError> { Ok(0) }
+		",
+	);
+	let wrapper = squash(&bindings.wrapper);
+
+	assert!(bindings.header.contains("TEST_CRATE_STATUS_ERROR = 1,"));
+	assert!(bindings.header.contains(
+		"// Writes the result to `out` when returning TEST_CRATE_STATUS_OK\ntest_crate_status parse(const char* text, uint32_t* out);"
+	));
+	assert!(wrapper.contains(
+		"if out.is_null() { set_last_error(\"`out` is NULL\"); return Status::NullPointer; }"
+	));
+	assert!(wrapper.contains(
+		"match result { Ok(value) => { unsafe { *out = value }; Status::Ok } Err(error) => { set_last_error(error); Status::Error } }"
+	));
+}
+
+#[test]
+fn unit_results_have_no_out_parameter() {
+	let bindings =
+		generate("pub fn check(n: u32) -> Result<(), String> { Ok(()) }");
+
+	assert!(bindings
+		.header
+		.contains("test_crate_status check(uint32_t n);"));
+}
+
+#[test]
+fn errors_are_described_by_display_then_debug() {
+	let bindings = generate(
+		"
+		#[derive(Debug)]
+		pub struct DebugError;
+		pub fn check(n: u32) -> Result<(), DebugError> { Ok(()) }
+		pub fn opaque(n: u32) -> Result<u8, ()> { Ok(0) }
+		",
+	);
+	let wrapper = squash(&bindings.wrapper);
+
+	assert!(wrapper.contains(
+		"Err(error) => { set_last_error(format!(\"{:?}\", error)); Status::Error }"
+	));
+	assert!(wrapper.contains(
+		"Err(_) => { set_last_error(\"the call failed\"); Status::Error }"
+	));
+}
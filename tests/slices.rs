@@ -0,0 +1,42 @@
+mod common;
+
+use common::{generate, squash};
+
+#[test]
+fn slices_are_passed_as_a_pointer_and_a_length() {
+	let bindings = generate(
+		"
+		pub fn sum(values: &[u32]) -> u32 { 0 }
+		pub fn fill(values: &mut [u8]) {}
+		",
+	);
+	let wrapper = squash(&bindings.wrapper);
+
+	assert!(bindings
+		.header
+		.contains("uint32_t sum(const uint32_t* values, size_t values_len);"));
+	assert!(bindings
+		.header
+		.contains("void fill(uint8_t* values, size_t values_len);"));
+	assert!(wrapper.contains(
+		"unsafe { core::slice::from_raw_parts(values, values_len) }"
+	));
+	assert!(wrapper.contains(
+		"unsafe { core::slice::from_raw_parts_mut(values, values_len) }"
+	));
+}
+
+#[test]
+fn empty_slices_may_be_null() {
+	let bindings = generate("pub fn sum(values: &[u32]) -> u32 { 0 }");
+	let wrapper = squash(&bindings.wrapper);
+
+	assert!(wrapper.contains(
+		"if values.is_null() && values_len != 0 { set_last_error(\"`values` is NULL but `values_len` is not 0\");"
+	));
+	assert!(wrapper.contains(
+		"if values_len > isize::MAX as usize / core::mem::size_of::<u32>().max(1) { set_last_error(\"`values_len` is too large\");"
+	));
+	assert!(wrapper
+		.contains("let values: &[u32] = if values_len == 0 { &[] } else {"));
+}
@@ -0,0 +1,63 @@
+mod common;
+
+use common::{generate, squash};
+
+const POINT: &str = "
+	#[repr(C)]
+	#[derive(Clone, Copy, Default)]
+	pub struct Point { pub x: f64, pub y: f64 }
+	pub fn shift(p: Point, by: &Point) -> Point { p }
+";
+
+#[test]
+fn repr_c_structs_are_passed_by_value() {
+	let bindings = generate(POINT);
+	let wrapper = squash(&bindings.wrapper);
+
+	assert!(bindings.header.contains(
+		"typedef struct point {\n    double x;\n    double y;\n} point;"
+	));
+	assert!(bindings
+		.header
+		.contains("point shift(point p, const point* by);"));
+	assert!(!bindings.header.contains("point_free"));
+	assert!(wrapper.contains(
+		"fn shift(p: test_crate::Point, by: *const test_crate::Point) -> test_crate::Point"
+	));
+}
+
+#[test]
+fn vtables_of_by_value_structs_free_nothing() {
+	let bindings = generate(&format!(
+		"{}
+		pub trait Shape {{ fn area(&self) -> f64; }}
+		impl Shape for Point {{ fn area(&self) -> f64 {{ self.x * self.y }} }}
+		",
+		POINT
+	));
+
+	assert!(bindings.header.contains(
+		"// `free` does nothing, as `point` values belong to the caller\nconst shape_vtable* point_shape_vtable(void);"
+	));
+	assert!(squash(&bindings.wrapper).contains(
+		"extern \"C\" fn point_shape_free(self_: *mut core::ffi::c_void) { let _ = self_; }"
+	));
+	assert!(!bindings
+		.wrapper
+		.contains("Box::from_raw(self_ as *mut test_crate::Point)"));
+}
+
+#[test]
+fn structs_with_other_fields_stay_opaque() {
+	let bindings = generate(
+		"
+		#[repr(C)]
+		pub struct Named { pub name: String }
+		pub fn rename(named: &mut Named) {}
+		",
+	);
+
+	assert!(bindings.header.contains("typedef struct named named;"));
+	assert!(bindings.header.contains("void rename(named* named);"));
+	assert!(bindings.header.contains("void named_free(named* obj);"));
+}
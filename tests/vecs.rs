@@ -0,0 +1,51 @@
+mod common;
+
+use common::{generate, squash};
+
+#[test]
+fn vecs_are_returned_as_owned_buffers() {
+	let bindings = generate(
+		"
+		pub fn numbers() -> Vec<u32> { vec![] }
+		pub fn total(values: Vec<u32>) -> u32 { 0 }
+		",
+	);
+	let header = squash(&bindings.header);
+	let wrapper = squash(&bindings.wrapper);
+
+	assert!(header.contains(
+		"typedef struct test_crate_vec_u32 { uint32_t* data; size_t len; size_t capacity; } test_crate_vec_u32;"
+	));
+	assert!(header
+		.contains("void test_crate_vec_u32_free(test_crate_vec_u32* vec);"));
+	assert!(header.contains("test_crate_vec_u32 numbers(void);"));
+	assert!(header.contains("uint32_t total(test_crate_vec_u32 values);"));
+	assert!(wrapper.contains("vec_u32_to_c(result)"));
+	assert!(wrapper.contains("let values = unsafe { vec_u32_from_c(values) };"));
+}
+
+#[test]
+fn freed_buffers_are_emptied() {
+	let bindings = generate("pub fn numbers() -> Vec<u32> { vec![] }");
+	let wrapper = squash(&bindings.wrapper);
+
+	assert!(wrapper.contains(
+		"if let Some(vec) = unsafe { vec.as_mut() } { drop(unsafe { vec_u32_from_c(*vec) }); vec.data = core::ptr::null_mut(); vec.len = 0; vec.capacity = 0; }"
+	));
+	assert!(wrapper.contains(
+		"if vec.data.is_null() { return Vec::new(); } Vec::from_raw_parts(vec.data, vec.len, vec.capacity)"
+	));
+}
+
+#[test]
+fn opaque_structs_are_not_stored_in_buffers() {
+	let bindings = generate(
+		"
+		pub struct Item { id: u32 }
+		pub fn items() -> Vec<Item> { vec![] }
+		",
+	);
+
+	assert!(bindings.warns(&["skipping function `items`", "C buffer"]));
+	assert!(!bindings.header.contains("vec_item"));
+}
@@ -0,0 +1,76 @@
+mod common;
+
+use common::{generate, squash};
+
+#[test]
+fn trait_impls_are_exported_as_vtables() {
+	let bindings = generate(
+		"
+		pub struct Circle { radius: f64 }
+		pub trait Shape {
+			fn area(&self) -> f64;
+			fn name(&self) -> String;
+		}
+		impl Shape for Circle {
+			fn area(&self) -> f64 { self.radius }
+			fn name(&self) -> String { String::new() }
+		}
+		",
+	);
+	let header = squash(&bindings.header);
+	let wrapper = squash(&bindings.wrapper);
+
+	assert!(header.contains(
+		"typedef struct shape_vtable { double (*area)(const void* self); char* (*name)(const void* self); // Releases the object `self` points to void (*free)(void* self); } shape_vtable;"
+	));
+	assert!(header.contains(
+		"typedef struct shape_dyn { void* self; const shape_vtable* vtable; } shape_dyn;"
+	));
+	assert!(header.contains("const shape_vtable* circle_shape_vtable(void);"));
+	assert!(wrapper
+		.contains("<test_crate::Circle as test_crate::Shape>::area(self_)"));
+	assert!(wrapper.contains(
+		"drop(unsafe { Box::from_raw(self_ as *mut test_crate::Circle) });"
+	));
+	assert!(wrapper.contains(
+		"static CIRCLE_SHAPE: CShapeVtable = CShapeVtable { area: circle_shape_area, name: circle_shape_name, free: circle_shape_free, };"
+	));
+}
+
+#[test]
+fn traits_with_unsupported_methods_are_skipped() {
+	let bindings = generate(
+		"
+		use std::collections::HashMap;
+		pub struct Circle { radius: f64 }
+		pub trait Weird { fn pair(&self) -> (i32, i32); }
+		impl Weird for Circle { fn pair(&self) -> (i32, i32) { (0, 0) } }
+		pub trait Mapped { fn map(&self) -> HashMap<u8, u8>; }
+		impl Mapped for Circle {
+			fn map(&self) -> HashMap<u8, u8> { HashMap::new() }
+		}
+		",
+	);
+
+	assert!(bindings.warns(&["skipping trait `Weird`", "in `pair`"]));
+	assert!(bindings.warns(&["skipping trait `Mapped`", "in `map`"]));
+	for name in ["weird", "mapped"] {
+		assert!(!bindings.header.contains(name));
+		assert!(!bindings.wrapper.contains(name));
+	}
+	assert!(!bindings.wrapper.contains("transmute"));
+}
+
+#[test]
+fn boxed_values_other_than_structs_and_traits_are_skipped() {
+	let bindings = generate(
+		"
+		pub enum Kind { A, B }
+		pub fn boxed() -> Box<Kind> { Box::new(Kind::A) }
+		",
+	);
+
+	assert!(bindings.warns(&["skipping function `boxed`"]));
+	assert!(!bindings.header.contains("boxed("));
+	assert!(!bindings.wrapper.contains("transmute"));
+}